@@ -0,0 +1,336 @@
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap, StorageValue, ensure};
+use support::traits::{Currency, ReservableCurrency};
+use system::ensure_signed;
+use parity_codec::{Decode, Encode};
+use rstd::prelude::*;
+use primitives::H256;
+
+// Bounds on the content of a DID document.
+const MAX_KEYS: usize = 8;
+const MAX_SERVICES: usize = 8;
+const MAX_FRAGMENT_LEN: usize = 32;
+const MAX_SERVICE_TYPE_LEN: usize = 64;
+const MAX_ENDPOINT_LEN: usize = 128;
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// The currency DID deposits are reserved in.
+    type Currency: ReservableCurrency<Self::AccountId>;
+}
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Method-specific identifier of a `did:substrate` DID.
+pub type Did = H256;
+
+/// The type of a verification key.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    Sr25519,
+    Ed25519,
+}
+
+/// A verification key published in a DID document.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct DidKey {
+    // Fragment identifying the key within the document, e.g. `key-1`.
+    pub id: Vec<u8>,
+    pub key_type: KeyType,
+    pub public_key: H256,
+}
+
+/// A service endpoint published in a DID document.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct DidService {
+    // Fragment identifying the service within the document.
+    pub id: Vec<u8>,
+    pub service_type: Vec<u8>,
+    pub endpoint: Vec<u8>,
+}
+
+/// A DID document.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct DidDocument<AccountId> {
+    // The account allowed to update the document.
+    pub controller: AccountId,
+    pub keys: Vec<DidKey>,
+    pub services: Vec<DidService>,
+    pub deactivated: bool,
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Did {
+        // Nonce making created DIDs unique.
+        DidNonce get(did_nonce): u64;
+        // DID documents.
+        Documents get(document): map Did => Option<DidDocument<T::AccountId>>;
+        // The active DID controlled by each account.
+        AccountDid get(did_of): map T::AccountId => Option<Did>;
+        // Deposit reserved from the creator of a DID.
+        DidDeposit get(did_deposit) config(): BalanceOf<T>;
+        // Reserved DID deposits - depositor and amount.
+        DidDeposits get(did_deposits): map Did => Option<(T::AccountId, BalanceOf<T>)>;
+    }
+    extra_genesis_skip_phantom_data_field;
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as system::Trait>::AccountId,
+    {
+        // A DID is created - did, controller
+        DidCreated(Did, AccountId),
+        // A DID document's keys or services are updated.
+        DidUpdated(Did),
+        // A DID is handed over to a new controller - did, new controller
+        DidControllerChanged(Did, AccountId),
+        // A DID is deactivated.
+        DidDeactivated(Did),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Create a DID controlled by the sender.
+        /// An account can control at most one active DID.
+        /// Reserves the DID deposit from the sender for as long as the
+        /// document is stored, which is for good as DIDs are never deleted.
+        pub fn create_did(origin, keys: Vec<DidKey>, services: Vec<DidService>) {
+            let sender = ensure_signed(origin)?;
+            ensure!(!<AccountDid<T>>::exists(&sender), "Account already controls a DID.");
+            Self::check_document(&keys, &services)?;
+
+            let nonce = Self::did_nonce();
+            let did = H256(runtime_io::blake2_256(&(&b"did:substrate"[..], &sender, nonce).encode()));
+            ensure!(!<Documents<T>>::exists(did), "DID already exists.");
+
+            let deposit = Self::did_deposit();
+            T::Currency::reserve(&sender, deposit)
+                .map_err(|_| "Insufficient balance for DID deposit.")?;
+            <DidDeposits<T>>::insert(did, (sender.clone(), deposit));
+
+            let document = DidDocument {
+                controller: sender.clone(),
+                keys,
+                services,
+                deactivated: false,
+            };
+            <Documents<T>>::insert(did, document);
+            <AccountDid<T>>::insert(&sender, did);
+            <DidNonce<T>>::put(nonce + 1);
+
+            Self::deposit_event(RawEvent::DidCreated(did, sender));
+        }
+
+        /// Replace the keys and services of a DID document.
+        /// Only the controller can call this function.
+        pub fn update_did(origin, did: Did, keys: Vec<DidKey>, services: Vec<DidService>) {
+            let sender = ensure_signed(origin)?;
+            let mut document = Self::controlled_document(did, &sender)?;
+            Self::check_document(&keys, &services)?;
+
+            document.keys = keys;
+            document.services = services;
+            <Documents<T>>::insert(did, document);
+
+            Self::deposit_event(RawEvent::DidUpdated(did));
+        }
+
+        /// Hand a DID over to a new controller account, e.g. to rotate
+        /// the key controlling the document without changing the DID.
+        /// The new controller can then claim the credentials the old account
+        /// holds for the DID with `verifiablecreds::claim_did_credentials`.
+        /// Only the controller can call this function.
+        pub fn set_did_controller(origin, did: Did, new_controller: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            let mut document = Self::controlled_document(did, &sender)?;
+            ensure!(!<AccountDid<T>>::exists(&new_controller), "Account already controls a DID.");
+
+            document.controller = new_controller.clone();
+            <Documents<T>>::insert(did, document);
+            <AccountDid<T>>::remove(&sender);
+            <AccountDid<T>>::insert(&new_controller, did);
+
+            Self::deposit_event(RawEvent::DidControllerChanged(did, new_controller));
+        }
+
+        /// Deactivate a DID for good.
+        /// Only the controller can call this function.
+        pub fn deactivate_did(origin, did: Did) {
+            let sender = ensure_signed(origin)?;
+            let mut document = Self::controlled_document(did, &sender)?;
+
+            document.deactivated = true;
+            <Documents<T>>::insert(did, document);
+            <AccountDid<T>>::remove(&sender);
+
+            Self::deposit_event(RawEvent::DidDeactivated(did));
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The active DID document controlled by `who`.
+    fn controlled_document(did: Did, who: &T::AccountId) -> rstd::result::Result<DidDocument<T::AccountId>, &'static str> {
+        let document = Self::document(did).ok_or("DID does not exist.")?;
+        ensure!(document.controller == *who, "Unauthorized.");
+        ensure!(!document.deactivated, "DID deactivated.");
+        Ok(document)
+    }
+
+    /// Check that keys and services are within bounds and have unique ids.
+    fn check_document(keys: &[DidKey], services: &[DidService]) -> Result {
+        ensure!(keys.len() <= MAX_KEYS, "Too many keys.");
+        ensure!(services.len() <= MAX_SERVICES, "Too many services.");
+        for (i, key) in keys.iter().enumerate() {
+            ensure!(key.id.len() <= MAX_FRAGMENT_LEN, "Key id too long.");
+            ensure!(!keys[..i].iter().any(|k| k.id == key.id), "Duplicate key id.");
+        }
+        for (i, service) in services.iter().enumerate() {
+            ensure!(service.id.len() <= MAX_FRAGMENT_LEN, "Service id too long.");
+            ensure!(service.service_type.len() <= MAX_SERVICE_TYPE_LEN, "Service type too long.");
+            ensure!(service.endpoint.len() <= MAX_ENDPOINT_LEN, "Service endpoint too long.");
+            ensure!(!services[..i].iter().any(|s| s.id == service.id), "Duplicate service id.");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use primitives::Blake2Hasher;
+  use runtime_io::with_externalities;
+  use runtime_primitives::{
+    testing::{Digest, DigestItem, Header},
+    traits::{BlakeTwo256, IdentityLookup},
+    BuildStorage,
+  };
+  use support::{assert_noop, assert_ok, impl_outer_origin};
+
+  impl_outer_origin! {
+    pub enum Origin for Test {}
+  }
+
+  #[derive(Clone, Eq, PartialEq)]
+  pub struct Test;
+  impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type Digest = Digest;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<u64>;
+    type Header = Header;
+    type Event = ();
+    type Log = DigestItem;
+  }
+  impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+  }
+  impl Trait for Test {
+    type Event = ();
+    type Currency = balances::Module<Test>;
+  }
+  type DidRegistry = Module<Test>;
+  type Balances = balances::Module<Test>;
+
+  fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::<Test>::default()
+      .build_storage()
+      .unwrap()
+      .0;
+    t.extend(
+      balances::GenesisConfig::<Test> {
+        transaction_base_fee: 0,
+        transaction_byte_fee: 0,
+        existential_deposit: 0,
+        transfer_fee: 0,
+        creation_fee: 0,
+        balances: vec![(1, 100), (2, 100)],
+        vesting: vec![],
+      }
+      .build_storage()
+      .unwrap()
+      .0,
+    );
+    t.extend(
+      GenesisConfig::<Test> {
+        did_deposit: 5,
+      }
+      .build_storage()
+      .unwrap()
+      .0,
+    );
+    t.into()
+  }
+
+  fn key(id: &[u8]) -> DidKey {
+    DidKey { id: id.to_vec(), key_type: KeyType::Sr25519, public_key: H256::repeat_byte(1) }
+  }
+
+  #[test]
+  fn should_create_and_update_did() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            DidRegistry::create_did(Origin::signed(1), vec![key(b"key-1")], vec![]));
+        let did = DidRegistry::did_of(1).unwrap();
+        assert_eq!(Balances::reserved_balance(&1), 5);
+        assert_noop!(
+            DidRegistry::create_did(Origin::signed(1), vec![], vec![]),
+            "Account already controls a DID.");
+        assert_noop!(
+            DidRegistry::create_did(Origin::signed(3), vec![], vec![]),
+            "Insufficient balance for DID deposit.");
+
+        assert_noop!(
+            DidRegistry::update_did(Origin::signed(2), did, vec![], vec![]),
+            "Unauthorized.");
+        assert_ok!(
+            DidRegistry::update_did(Origin::signed(1), did, vec![key(b"key-2")], vec![]));
+        assert_eq!(
+            DidRegistry::document(did).unwrap().keys, vec![key(b"key-2")]);
+    });
+  }
+
+  #[test]
+  fn should_change_controller_and_deactivate() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            DidRegistry::create_did(Origin::signed(1), vec![], vec![]));
+        let did = DidRegistry::did_of(1).unwrap();
+
+        assert_ok!(
+            DidRegistry::set_did_controller(Origin::signed(1), did, 2));
+        assert_eq!(DidRegistry::did_of(1), None);
+        assert_eq!(DidRegistry::did_of(2), Some(did));
+
+        assert_ok!(
+            DidRegistry::deactivate_did(Origin::signed(2), did));
+        assert_eq!(DidRegistry::did_of(2), None);
+        assert_noop!(
+            DidRegistry::update_did(Origin::signed(2), did, vec![], vec![]),
+            "DID deactivated.");
+        // The deposit stays reserved from the creator with the deactivated document.
+        assert_eq!(DidRegistry::did_deposits(did), Some((1, 5)));
+        assert_eq!(Balances::reserved_balance(&1), 5);
+    });
+  }
+}
@@ -0,0 +1,15 @@
+//! Runtime API for resolving `did:substrate` DIDs.
+
+use client::decl_runtime_apis;
+use crate::AccountId;
+use crate::did::{Did, DidDocument};
+
+decl_runtime_apis! {
+    /// The API to resolve DIDs.
+    pub trait DidApi {
+        /// The DID document of the DID, if it exists.
+        fn document(did: Did) -> Option<DidDocument<AccountId>>;
+        /// The active DID controlled by the account, if any.
+        fn did_of(account: AccountId) -> Option<Did>;
+    }
+}
@@ -57,7 +57,18 @@ pub type BlockNumber = u64;
 /// Index of an account's extrinsic in the chain.
 pub type Nonce = u64;
 
-mod verifiablecreds;
+/// A timestamp: seconds since the unix epoch.
+pub type Moment = u64;
+
+pub mod did;
+pub mod did_api;
+pub mod merkle;
+pub mod verifiablecreds;
+pub mod verifiablecreds_api;
+
+use did::{Did, DidDocument};
+use merkle::{ClaimProof, SaltedClaim};
+use verifiablecreds::{Credential, Prerequisites, Retirement, Revocation, StatusList, StatusListId, Subject, SubjectInfo};
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
@@ -100,7 +111,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("substrate-verifiable-credentials"),
     impl_name: create_runtime_str!("substrate-verifiable-credentials"),
     authoring_version: 3,
-    spec_version: 4,
+    spec_version: 5,
     impl_version: 4,
     apis: RUNTIME_API_VERSIONS,
 };
@@ -167,7 +178,7 @@ impl indices::Trait for Runtime {
 
 impl timestamp::Trait for Runtime {
     /// A timestamp: seconds since the unix epoch.
-    type Moment = u64;
+    type Moment = Moment;
     type OnTimestampSet = Aura;
 }
 
@@ -192,8 +203,17 @@ impl sudo::Trait for Runtime {
     type Proposal = Call;
 }
 
+impl did::Trait for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+}
+
 impl verifiablecreds::Trait for Runtime {
     type Event = Event;
+    type Currency = Balances;
+    /// Any account may create subjects. Networks where only approved
+    /// organisations may do so use `verifiablecreds::AllowlistCreation`.
+    type CreateSubjectOrigin = verifiablecreds::OpenCreation;
 }
 
 construct_runtime!(
@@ -209,6 +229,7 @@ construct_runtime!(
 		Indices: indices,
 		Balances: balances,
 		Sudo: sudo,
+		DidRegistry: did::{Module, Call, Storage, Event<T>, Config<T>},
 		VerifiableCreds: verifiablecreds::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
@@ -302,4 +323,84 @@ impl_runtime_apis! {
             Consensus::authorities()
         }
     }
+
+    impl verifiablecreds_api::VerifiableCredsApi<Block> for Runtime {
+        fn is_valid(holder: AccountId, subject: Subject) -> bool {
+            VerifiableCreds::check_credential(&holder, subject).is_ok()
+        }
+
+        fn credential(holder: AccountId, subject: Subject) -> Option<Credential<Moment, AccountId>> {
+            VerifiableCreds::issued_credential(&holder, subject)
+        }
+
+        fn subject_issuer(subject: Subject) -> Option<AccountId> {
+            VerifiableCreds::subject_owner(subject)
+        }
+
+        fn verify(holder: AccountId, subject: Subject) -> Result<(), Vec<u8>> {
+            VerifiableCreds::check_credential(&holder, subject).map_err(|e| e.as_bytes().to_vec())
+        }
+
+        fn verify_document(holder: AccountId, subject: Subject, document: Vec<u8>) -> Result<(), Vec<u8>> {
+            VerifiableCreds::check_credential(&holder, subject)
+                .and_then(|_| VerifiableCreds::check_document(&holder, subject, &document))
+                .map_err(|e| e.as_bytes().to_vec())
+        }
+
+        fn verify_claim(holder: AccountId, subject: Subject, claim: SaltedClaim, proof: ClaimProof) -> Result<(), Vec<u8>> {
+            VerifiableCreds::check_credential(&holder, subject)
+                .and_then(|_| VerifiableCreds::check_claim_proof(&holder, subject, &claim, &proof))
+                .map_err(|e| e.as_bytes().to_vec())
+        }
+
+        fn revocation(holder: AccountId, subject: Subject) -> Option<Revocation<Moment, AccountId>> {
+            VerifiableCreds::revocations((holder, subject))
+        }
+
+        fn subject_info(subject: Subject) -> SubjectInfo {
+            VerifiableCreds::subject_info(subject)
+        }
+
+        fn subject_retirement(subject: Subject) -> Option<Retirement<Moment>> {
+            VerifiableCreds::retired_subjects(subject)
+        }
+
+        fn subject_prerequisites(subject: Subject) -> Prerequisites {
+            VerifiableCreds::subject_prerequisites(subject)
+        }
+
+        fn is_transferable(subject: Subject) -> bool {
+            VerifiableCreds::is_transferable(subject)
+        }
+
+        fn subject_issuers(subject: Subject) -> Vec<AccountId> {
+            VerifiableCreds::subject_issuers(subject)
+        }
+
+        fn holder_credentials(holder: AccountId, start: u32, limit: u32) -> Vec<Subject> {
+            VerifiableCreds::holder_subjects(&holder, start, limit)
+        }
+
+        fn subject_holders(subject: Subject, start: u32, limit: u32) -> Vec<AccountId> {
+            VerifiableCreds::subject_holders(subject, start, limit)
+        }
+
+        fn accreditation_chain(holder: AccountId, subject: Subject) -> Option<Vec<AccountId>> {
+            VerifiableCreds::credential_accreditation(&holder, subject)
+        }
+
+        fn status_list(list: StatusListId) -> Option<StatusList<Moment, AccountId>> {
+            VerifiableCreds::status_lists(list)
+        }
+    }
+
+    impl did_api::DidApi<Block> for Runtime {
+        fn document(did: Did) -> Option<DidDocument<AccountId>> {
+            DidRegistry::document(did)
+        }
+
+        fn did_of(account: AccountId) -> Option<Did> {
+            DidRegistry::did_of(account)
+        }
+    }
 }
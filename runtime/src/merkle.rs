@@ -0,0 +1,125 @@
+//! Merkle commitments to credential claims, for selective disclosure.
+//!
+//! An issuer commits to a credential's claims by recording their Merkle root
+//! in the credential. The holder keeps the salted claims off-chain and later
+//! discloses a single claim with an inclusion proof, revealing nothing about
+//! the other claims.
+//!
+//! Leaves are `blake2_256(0x00 ++ salt ++ encode(claim))` and inner nodes are
+//! `blake2_256(0x01 ++ left ++ right)`. The salt keeps undisclosed claims with
+//! few possible values, e.g. booleans, from being guessed from their hashes.
+//! A node without a sibling is carried up to the next level unchanged.
+
+use parity_codec::{Decode, Encode};
+use rstd::prelude::*;
+use primitives::H256;
+use crate::verifiablecreds::Claim;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// A claim along with the salt its leaf is hashed with.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq)]
+pub struct SaltedClaim {
+    pub claim: Claim,
+    pub salt: H256,
+}
+
+/// Proof that a claim is included in a claims root.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct ClaimProof {
+    // Position of the claim's leaf.
+    pub index: u32,
+    // Number of leaves in the tree.
+    pub leaf_count: u32,
+    // Siblings on the path from the leaf to the root, bottom up.
+    pub path: Vec<H256>,
+}
+
+/// The leaf hash of a salted claim.
+pub fn leaf_hash(claim: &SaltedClaim) -> H256 {
+    let mut data = vec![LEAF_PREFIX];
+    data.extend_from_slice(&claim.salt.0);
+    data.extend(claim.claim.encode());
+    H256(runtime_io::blake2_256(&data))
+}
+
+fn node_hash(left: &H256, right: &H256) -> H256 {
+    let mut data = vec![NODE_PREFIX];
+    data.extend_from_slice(&left.0);
+    data.extend_from_slice(&right.0);
+    H256(runtime_io::blake2_256(&data))
+}
+
+/// The level above `level`.
+fn parent_level(level: &[H256]) -> Vec<H256> {
+    level.chunks(2).map(|pair| match pair {
+        [left, right] => node_hash(left, right),
+        [single] => *single,
+        _ => unreachable!("chunks(2) yields one or two nodes; qed"),
+    }).collect()
+}
+
+/// The Merkle root committing to the claims, or `None` if there are none.
+pub fn claims_root(claims: &[SaltedClaim]) -> Option<H256> {
+    let mut level: Vec<H256> = claims.iter().map(leaf_hash).collect();
+    while level.len() > 1 {
+        level = parent_level(&level);
+    }
+    level.pop()
+}
+
+/// Prove that the claim at `index` is included in the root of `claims`.
+pub fn prove_claim(claims: &[SaltedClaim], index: usize) -> Option<ClaimProof> {
+    if index >= claims.len() {
+        return None;
+    }
+
+    let mut level: Vec<H256> = claims.iter().map(leaf_hash).collect();
+    let mut position = index;
+    let mut path = Vec::new();
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            path.push(level[sibling]);
+        }
+        level = parent_level(&level);
+        position /= 2;
+    }
+
+    Some(ClaimProof {
+        index: index as u32,
+        leaf_count: claims.len() as u32,
+        path,
+    })
+}
+
+/// Verify that `claim` is included in `root` according to `proof`.
+pub fn verify_claim(root: &H256, claim: &SaltedClaim, proof: &ClaimProof) -> bool {
+    if proof.index >= proof.leaf_count {
+        return false;
+    }
+
+    let mut hash = leaf_hash(claim);
+    let mut siblings = proof.path.iter();
+    let (mut position, mut width) = (proof.index, proof.leaf_count);
+    while width > 1 {
+        if position % 2 == 1 {
+            match siblings.next() {
+                Some(left) => hash = node_hash(left, &hash),
+                None => return false,
+            }
+        } else if position + 1 < width {
+            match siblings.next() {
+                Some(right) => hash = node_hash(&hash, right),
+                None => return false,
+            }
+        }
+        position /= 2;
+        width = (width + 1) / 2;
+    }
+
+    siblings.next().is_none() && hash == *root
+}
@@ -1,32 +1,375 @@
-use support::{decl_event, decl_module, decl_storage, StorageMap, StorageValue, ensure};
-use system::ensure_signed;
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap, StorageValue, ensure};
+use support::traits::{Currency, ReservableCurrency};
+use system::{ensure_root, ensure_signed};
 use parity_codec::{Decode, Encode};
+use rstd::prelude::*;
+use runtime_primitives::traits::As;
+use primitives::H256;
+use crate::did::{self, Did};
+use crate::merkle::{self, ClaimProof, SaltedClaim};
 use core::u32::MAX as MAX_SUBJECT;
 
-pub trait Trait: system::Trait + timestamp::Trait {
+// Bounds on the claims attached to a single credential.
+const MAX_CLAIMS: usize = 16;
+const MAX_CLAIM_KEY_LEN: usize = 32;
+const MAX_CLAIM_VALUE_LEN: usize = 256;
+// Bounds on the metadata describing a subject.
+const MAX_SUBJECT_NAME_LEN: usize = 64;
+const MAX_SUBJECT_DESCRIPTION_LEN: usize = 256;
+// Bound on the extra issuers authorized for a subject.
+const MAX_SUBJECT_ISSUERS: usize = 16;
+// Bound on the open credential requests per issuer.
+const MAX_OPEN_REQUESTS: usize = 1024;
+// Bound on the credentials issued or revoked in a single batch.
+const MAX_BATCH_SIZE: usize = 256;
+// Bound on the entries returned by a single index page.
+const MAX_PAGE_SIZE: u32 = 100;
+// Bounds on the prerequisites of a subject and the subjects depending on one.
+const MAX_PREREQUISITES: usize = 8;
+const MAX_DEPENDENT_SUBJECTS: usize = 64;
+// Layout version of this module's storage.
+// 0: credentials are `CredentialV0` and not indexed.
+// 1: credentials are `Credential` and indexed by holder and subject.
+const STORAGE_VERSION: u32 = 1;
+// Bound on the credentials migrated in a single block.
+const MAX_MIGRATIONS_PER_BLOCK: usize = 100;
+// Bounds on status lists: their entries, capped at 16KB worth of entries,
+// and the entries updated in a single call. The W3C recommends lists of at
+// least that size for herd privacy; smaller lists are allowed but leak more.
+const MAX_STATUS_LIST_SIZE: u32 = 131_072;
+const MAX_STATUS_UPDATES: usize = 1024;
+
+pub trait Trait: system::Trait + timestamp::Trait + did::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// The currency deposits for subjects and credentials are reserved in.
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// Who may create subjects: `OpenCreation`, `SudoCreation` or `AllowlistCreation`.
+    type CreateSubjectOrigin: EnsureSubjectCreator<Self>;
+}
+
+/// Decides which origins may create subjects.
+pub trait EnsureSubjectCreator<T: Trait> {
+    /// The account creating a subject, if the origin may create one.
+    fn ensure_creator(origin: T::Origin) -> rstd::result::Result<T::AccountId, &'static str>;
+}
+
+/// Any signed origin may create subjects.
+pub struct OpenCreation;
+
+impl<T: Trait> EnsureSubjectCreator<T> for OpenCreation {
+    fn ensure_creator(origin: T::Origin) -> rstd::result::Result<T::AccountId, &'static str> {
+        ensure_signed(origin)
+    }
+}
+
+/// Only the sudo key may create subjects.
+pub struct SudoCreation;
+
+impl<T: Trait + sudo::Trait> EnsureSubjectCreator<T> for SudoCreation {
+    fn ensure_creator(origin: T::Origin) -> rstd::result::Result<T::AccountId, &'static str> {
+        let sender = ensure_signed(origin)?;
+        ensure!(sender == <sudo::Module<T>>::key(), "Not allowed to create subjects.");
+        Ok(sender)
+    }
+}
+
+/// Only accounts on the subject creator allowlist may create subjects.
+pub struct AllowlistCreation;
+
+impl<T: Trait> EnsureSubjectCreator<T> for AllowlistCreation {
+    fn ensure_creator(origin: T::Origin) -> rstd::result::Result<T::AccountId, &'static str> {
+        let sender = ensure_signed(origin)?;
+        ensure!(<Module<T>>::subject_creators(&sender), "Not allowed to create subjects.");
+        Ok(sender)
+    }
 }
 
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
 pub type Subject = u32;
 
+/// Issuer-defined code explaining why a credential was revoked.
+pub type ReasonCode = u16;
+
+/// The type of a claim, as declared in a subject's schema.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimType {
+    Bool,
+    U64,
+    Text,
+    Bytes,
+}
+
+/// The value of a single claim.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub enum ClaimValue {
+    Bool(bool),
+    U64(u64),
+    Text(Vec<u8>),
+    Bytes(Vec<u8>),
+}
+
+impl ClaimValue {
+    pub fn claim_type(&self) -> ClaimType {
+        match self {
+            ClaimValue::Bool(_) => ClaimType::Bool,
+            ClaimValue::U64(_) => ClaimType::U64,
+            ClaimValue::Text(_) => ClaimType::Text,
+            ClaimValue::Bytes(_) => ClaimType::Bytes,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            ClaimValue::Bool(_) | ClaimValue::U64(_) => 0,
+            ClaimValue::Text(v) | ClaimValue::Bytes(v) => v.len(),
+        }
+    }
+}
+
+/// A key/value claim attested by a credential, e.g. `class => Text("B")`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct Claim {
+    pub key: Vec<u8>,
+    pub value: ClaimValue,
+}
+
+/// A field of a subject's claim schema.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct SchemaField {
+    pub name: Vec<u8>,
+    pub ty: ClaimType,
+    pub required: bool,
+}
+
+/// Metadata describing what a subject attests.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct SubjectInfo {
+    pub name: Vec<u8>,
+    pub description: Vec<u8>,
+    // The claims a credential for this subject may carry.
+    pub schema: Vec<SchemaField>,
+}
+
+/// What happens to a credential when the holder's credential
+/// for one of its prerequisites is revoked.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+pub enum PrerequisiteRevocation {
+    // The credential is revoked along with the prerequisite.
+    Cascade,
+    // The credential is suspended until an issuer reinstates or revokes it.
+    FlagForReview,
+}
+
+impl Default for PrerequisiteRevocation {
+    fn default() -> Self {
+        PrerequisiteRevocation::FlagForReview
+    }
+}
+
+/// Subjects a holder must hold valid credentials for before being issued a credential.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct Prerequisites {
+    pub subjects: Vec<Subject>,
+    pub on_revoke: PrerequisiteRevocation,
+}
+
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, Clone, Default, PartialEq)]
 pub struct Credential<Timestamp, AccountId> {
-   subject: Subject,
-   when: Timestamp,
-   by: AccountId
+    pub subject: Subject,
+    pub when: Timestamp,
+    pub by: AccountId,
+    // The credential is not valid before this moment.
+    pub valid_from: Timestamp,
+    // The credential is not valid from this moment on, if set.
+    pub valid_until: Option<Timestamp>,
+    // The claims attested by this credential.
+    pub claims: Vec<Claim>,
+    // Whether the credential is temporarily frozen by an issuer.
+    pub suspended: bool,
+    // Blake2-256 hash of the off-chain credential document, if anchored.
+    pub document_hash: Option<H256>,
+    // Merkle root of salted off-chain claims, for selective disclosure.
+    pub claims_root: Option<H256>,
+    // The issuer's DID at the time of issuance, if the issuer controls one.
+    pub issuer_did: Option<Did>,
+    // The holder's DID at the time of acceptance, if the holder controls one.
+    pub holder_did: Option<Did>
+}
+
+/// Layout of credentials in storage version 0.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+struct CredentialV0<Timestamp, AccountId> {
+    subject: Subject,
+    when: Timestamp,
+    by: AccountId,
+}
+
+/// A credential offered by an issuer, awaiting the holder's consent.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct CredentialOffer<Timestamp, AccountId> {
+    pub credential: Credential<Timestamp, AccountId>,
+    // The offer can no longer be accepted from this moment on.
+    pub expires: Timestamp,
+}
+
+/// Record of a retired subject.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct Retirement<Timestamp> {
+    pub when: Timestamp,
+    // Whether existing credentials were invalidated
+    // rather than kept as historical records.
+    pub invalidated: bool,
+}
+
+/// A credential requested by a holder from a subject's issuer.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct CredentialRequest<Hash, Timestamp> {
+    // Hash of off-chain evidence supporting the request.
+    pub evidence_hash: Hash,
+    pub when: Timestamp,
+}
+
+/// Category of subjects issuers are accredited for, e.g. nursing licences.
+pub type Category = u32;
+
+/// Accreditation of an issuer for a category of subjects.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct Accreditation<AccountId> {
+    // The accrediting issuer, or `None` if accredited by the root authority.
+    pub by: Option<AccountId>,
+    // Distance from the root authority, 1 for issuers it accredits directly.
+    pub depth: u32,
+}
+
+/// Identifier of a status list.
+pub type StatusListId = u32;
+
+/// A revocation bitmap for credentials signed off-chain.
+/// Each credential is assigned an index in the list, whose bit
+/// is set once the credential is revoked.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct StatusList<Timestamp, AccountId> {
+    pub subject: Subject,
+    // The issuer maintaining the list.
+    pub owner: AccountId,
+    // Number of entries in the list.
+    pub size: u32,
+    // Packed entries, the first entry being the most significant bit of the first byte.
+    pub bits: Vec<u8>,
+    // Last time entries were set or cleared.
+    pub updated: Timestamp,
+}
+
+/// Record of a revoked credential.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Default, PartialEq)]
+pub struct Revocation<Timestamp, AccountId> {
+    pub by: AccountId,
+    pub when: Timestamp,
+    pub reason: ReasonCode,
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as VerifiableCreds {
+        // Layout version of this module's storage.
+        // Chains started before the storage was versioned read 0.
+        StorageVersion get(storage_version) build(|_: &GenesisConfig<T>| STORAGE_VERSION): u32;
+        // Credentials awaiting migration to the current layout, as (holder, subject).
+        PendingMigrationCount get(pending_migration_count): u32;
+        PendingMigrations get(pending_migration): map u32 => (T::AccountId, Subject);
+        // Whether all credentials awaiting migration have been queued.
+        MigrationsQueued get(migrations_queued): bool;
         // global nonce for subject count
         SubjectCount get(subject_count) config(): Subject;
         // Issuers can issue credentials to others.
-        // Issuer to Subject mapping.
+        // Subject to owner mapping.
         Subjects get(subjects) config(): map Subject => T::AccountId;
+        // Accounts allowed to create subjects under `AllowlistCreation`.
+        SubjectCreators get(subject_creators) config(): map T::AccountId => bool;
+        // Additional issuers authorized by the subject owner.
+        SubjectIssuers get(subject_issuers): map Subject => Vec<T::AccountId>;
+        // Subjects that no longer accept new issuance.
+        RetiredSubjects get(retired_subjects): map Subject => Option<Retirement<T::Moment>>;
+        // Name, description and claim schema of each subject.
+        SubjectInfos get(subject_info): map Subject => SubjectInfo;
+        // Prerequisites of each subject.
+        SubjectPrerequisites get(subject_prerequisites): map Subject => Prerequisites;
+        // Subjects whose credentials holders may transfer.
+        TransferableSubjects get(is_transferable): map Subject => bool;
+        // Pending credential transfers.
+        // Mapping (holder, subject) to the recipient who has to accept the transfer.
+        PendingTransfers get(pending_transfer): map (T::AccountId, Subject) => Option<T::AccountId>;
+        // Subjects having each subject as a prerequisite.
+        DependentSubjects get(dependent_subjects): map Subject => Vec<Subject>;
         // Credentials store.
         // Mapping (holder, subject) to Credential.
         Credentials get(credentials): map (T::AccountId, Subject) => Credential<T::Moment, T::AccountId>;
+        // Index of the subjects each holder holds an unrevoked credential for.
+        HolderSubjectsArray get(holder_subject_by_index): map (T::AccountId, u32) => Subject;
+        HolderSubjectsCount get(holder_subjects_count): map T::AccountId => u32;
+        HolderSubjectsIndex: map (T::AccountId, Subject) => u32;
+        // Index of the holders of an unrevoked credential for each subject.
+        SubjectHoldersArray get(subject_holder_by_index): map (Subject, u32) => T::AccountId;
+        SubjectHoldersCount get(subject_holders_count): map Subject => u32;
+        SubjectHoldersIndex: map (T::AccountId, Subject) => u32;
+        // Deposit reserved from the creator of a subject.
+        SubjectDeposit get(subject_deposit) config(): BalanceOf<T>;
+        // Deposit reserved from the issuer of a credential while it is stored.
+        CredentialDeposit get(credential_deposit) config(): BalanceOf<T>;
+        // Reserved subject deposits - depositor and amount.
+        SubjectDeposits get(subject_deposits): map Subject => Option<(T::AccountId, BalanceOf<T>)>;
+        // Reserved deposits of pending offers - depositor and amount.
+        OfferDeposits get(offer_deposits): map (T::AccountId, Subject) => Option<(T::AccountId, BalanceOf<T>)>;
+        // Reserved deposits of stored credentials - depositor and amount.
+        CredentialDeposits get(credential_deposits): map (T::AccountId, Subject) => Option<(T::AccountId, BalanceOf<T>)>;
+        // Pending credential offers.
+        // Mapping (holder, subject) to an offer the holder can accept or reject.
+        CredentialOffers get(credential_offers): map (T::AccountId, Subject) => Option<CredentialOffer<T::Moment, T::AccountId>>;
+        // How long a credential offer can be accepted for.
+        OfferTimeout get(offer_timeout) config(): T::Moment;
+        // Pending credential requests.
+        // Mapping (holder, subject) to a request awaiting the issuer's decision.
+        CredentialRequests get(credential_requests): map (T::AccountId, Subject) => Option<CredentialRequest<T::Hash, T::Moment>>;
+        // Open requests, as (holder, subject), per subject owner.
+        // Additional issuers can approve or deny requests but are not indexed here;
+        // they find them through the owner's list or `CredentialRequested` events.
+        IssuerRequests get(issuer_requests): map T::AccountId => Vec<(T::AccountId, Subject)>;
+        // Deposit reserved from the holder of an open credential request.
+        RequestDeposit get(request_deposit) config(): BalanceOf<T>;
+        // Reserved deposits of open requests - depositor and amount.
+        RequestDeposits get(request_deposits): map (T::AccountId, Subject) => Option<(T::AccountId, BalanceOf<T>)>;
+        // Revocation registry.
+        // Mapping (holder, subject) to the revocation of its Credential.
+        Revocations get(revocations): map (T::AccountId, Subject) => Option<Revocation<T::Moment, T::AccountId>>;
+        // Trust registry.
+        // Mapping (issuer, category) to the issuer's accreditation.
+        Accreditations get(accreditations): map (T::AccountId, Category) => Option<Accreditation<T::AccountId>>;
+        // Category of each categorized subject.
+        SubjectCategories get(subject_category): map Subject => Option<Category>;
+        // How far accreditation can be delegated from the root authority.
+        MaxAccreditationDepth get(max_accreditation_depth) config(): u32;
+        // Revocation bitmaps for credentials signed off-chain.
+        StatusListCount get(status_list_count): StatusListId;
+        StatusLists get(status_lists): map StatusListId => Option<StatusList<T::Moment, T::AccountId>>;
+        // Deposit reserved from the creator of a status list per byte of its bitmap.
+        StatusListByteDeposit get(status_list_byte_deposit) config(): BalanceOf<T>;
+        // Reserved status list deposits - depositor and amount.
+        StatusListDeposits get(status_list_deposits): map StatusListId => Option<(T::AccountId, BalanceOf<T>)>;
     }
     extra_genesis_skip_phantom_data_field;
 }
@@ -35,13 +378,70 @@ decl_event!(
     pub enum Event<T>
     where
         AccountId = <T as system::Trait>::AccountId,
+        Hash = <T as system::Trait>::Hash,
     {
-        // A credential is issued - holder, subj, issuer
-        CredentialIssued(AccountId, Subject, AccountId),
-        // A credential is revoked - holder, subj, issuer
-        CredentialRevoked(AccountId, Subject, AccountId),
+        // A credential is offered to a holder - holder, subj, issuer
+        CredentialOffered(AccountId, Subject, AccountId),
+        // A credential offer is rejected by the holder - holder, subj, issuer
+        CredentialOfferRejected(AccountId, Subject, AccountId),
+        // A credential offer is withdrawn by the issuer - holder, subj, issuer
+        CredentialOfferWithdrawn(AccountId, Subject, AccountId),
+        // An offer that can no longer be accepted is cleared - holder, subj, issuer
+        CredentialOfferCleared(AccountId, Subject, AccountId),
+        // A credential is requested by a holder - holder, subj, subject owner, evidence hash
+        CredentialRequested(AccountId, Subject, AccountId, Hash),
+        // A credential request is denied - holder, subj, issuer, reason
+        CredentialRequestDenied(AccountId, Subject, AccountId, ReasonCode),
+        // A credential request is cancelled by the holder - holder, subj
+        CredentialRequestCancelled(AccountId, Subject),
+        // A credential is issued - holder, subj, issuer, claims
+        CredentialIssued(AccountId, Subject, AccountId, Vec<Claim>),
+        // A credential is revoked - holder, subj, issuer, reason
+        CredentialRevoked(AccountId, Subject, AccountId, ReasonCode),
+        // A credential is suspended - holder, subj, issuer
+        CredentialSuspended(AccountId, Subject, AccountId),
+        // A suspended credential is reinstated - holder, subj, issuer
+        CredentialReinstated(AccountId, Subject, AccountId),
         // A new subject is created.
         SubjectCreated(AccountId, Subject),
+        // Subject ownership is transferred - subj, old owner, new owner
+        SubjectTransferred(Subject, AccountId, AccountId),
+        // An issuer is authorized for a subject - subj, issuer
+        SubjectIssuerAdded(Subject, AccountId),
+        // An issuer is no longer authorized for a subject - subj, issuer
+        SubjectIssuerRemoved(Subject, AccountId),
+        // An account is allowed to create subjects.
+        SubjectCreatorAdded(AccountId),
+        // An account is no longer allowed to create subjects.
+        SubjectCreatorRemoved(AccountId),
+        // The prerequisites of a subject are set - subj, prerequisites
+        SubjectPrerequisitesSet(Subject, Vec<Subject>),
+        // A credential is suspended for review as a prerequisite is revoked - holder, subj, prerequisite
+        CredentialFlaggedForReview(AccountId, Subject, Subject),
+        // A subject's credentials are made transferable or not - subj, transferable
+        SubjectTransferabilitySet(Subject, bool),
+        // A credential transfer awaits the recipient's consent - holder, recipient, subj
+        CredentialTransferOffered(AccountId, AccountId, Subject),
+        // A pending credential transfer is cancelled - holder, recipient, subj
+        CredentialTransferCancelled(AccountId, AccountId, Subject),
+        // A credential is transferred - old holder, new holder, subj
+        CredentialTransferred(AccountId, AccountId, Subject),
+        // A subject is retired - subj, whether its credentials were invalidated
+        SubjectRetired(Subject, bool),
+        // An issuer is accredited - issuer, category, accrediting issuer (none for the root authority)
+        IssuerAccredited(AccountId, Category, Option<AccountId>),
+        // An accreditation is withdrawn - issuer, category
+        AccreditationRevoked(AccountId, Category),
+        // A subject is categorized - subj, category
+        SubjectCategorized(Subject, Category),
+        // The storage is migrated to a new layout version.
+        StorageMigrated(u32),
+        // A status list is created - list, subj, owner
+        StatusListCreated(StatusListId, Subject, AccountId),
+        // Status list entries are updated - list, indices, whether they are now revoked
+        StatusListUpdated(StatusListId, Vec<u32>, bool),
+        // A status list is removed - list, by
+        StatusListRemoved(StatusListId, AccountId),
     }
 );
 
@@ -49,42 +449,359 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
 
-        /// Issue a credential to an identity.
+        /// Migrate a batch of queued credentials while the storage is outdated.
+        fn on_initialize(_n: T::BlockNumber) {
+            if Self::storage_version() < STORAGE_VERSION {
+                Self::migrate_credentials();
+            }
+        }
+
+        /// Queue credentials stored in an outdated layout for migration,
+        /// which proceeds in batches at the start of each block.
+        /// Maps cannot be enumerated, so their keys are collected off-chain,
+        /// e.g. from `CredentialIssued` events. `complete` marks the last keys,
+        /// after which the storage version is bumped once all are migrated.
+        /// Only the root origin can call this function.
+        pub fn queue_credential_migrations(origin, keys: Vec<(T::AccountId, Subject)>, complete: bool) {
+            ensure_root(origin)?;
+            ensure!(Self::storage_version() < STORAGE_VERSION, "Storage already migrated.");
+            ensure!(keys.len() <= MAX_BATCH_SIZE, "Batch too large.");
+
+            let count = Self::pending_migration_count();
+            let new_count = count.checked_add(keys.len() as u32)
+                .ok_or("Overflow queueing credential migrations.")?;
+            for (i, key) in keys.into_iter().enumerate() {
+                <PendingMigrations<T>>::insert(count + i as u32, key);
+            }
+            <PendingMigrationCount<T>>::put(new_count);
+            if complete {
+                <MigrationsQueued<T>>::put(true);
+            }
+        }
+
+        /// Offer a credential to an identity.
         /// Only an issuer can call this function.
-        pub fn issue_credential(origin, to: T::AccountId, subject: Subject) {
+        /// The credential is valid from `valid_from` (defaults to now)
+        /// until `valid_until` (defaults to forever) and attests `claims`.
+        /// It is only issued once the holder accepts the offer.
+        /// `document_hash` anchors the off-chain credential document.
+        /// `claims_root` commits to salted off-chain claims the holder can
+        /// disclose one by one with Merkle proofs.
+        pub fn issue_credential(
+            origin,
+            to: T::AccountId,
+            subject: Subject,
+            valid_from: Option<T::Moment>,
+            valid_until: Option<T::Moment>,
+            claims: Vec<Claim>,
+            document_hash: Option<H256>,
+            claims_root: Option<H256>
+        ) {
             // Check if origin is an issuer.
-            // Issue the credential - add to storage.
+            // Offer the credential - add to pending offers.
+
+            let sender = ensure_signed(origin)?;
+            let cred = Self::new_credential(&sender, subject, valid_from, valid_until, claims, document_hash, claims_root)?;
+            Self::check_prerequisites(&to, subject)?;
+            ensure!(
+                <T as Trait>::Currency::can_reserve(&sender, Self::credential_deposit()),
+                "Insufficient balance for credential deposit."
+            );
+
+            Self::offer_credential(to, cred)?;
+        }
+
+        /// Offer credentials for a subject to many identities at once,
+        /// each with its own claims.
+        /// Either all credentials are offered or none is.
+        /// Only an issuer can call this function.
+        pub fn issue_credentials_batch(origin, subject: Subject, credentials: Vec<(T::AccountId, Vec<Claim>)>) {
+            let sender = ensure_signed(origin)?;
+            ensure!(credentials.len() <= MAX_BATCH_SIZE, "Batch too large.");
+
+            // Check every credential before offering any.
+            let mut offers = Vec::with_capacity(credentials.len());
+            for (to, claims) in credentials {
+                ensure!(!offers.iter().any(|(t, _)| *t == to), "Duplicate holder in batch.");
+                let cred = Self::new_credential(&sender, subject, None, None, claims, None, None)?;
+                Self::check_prerequisites(&to, subject)?;
+                offers.push((to, cred));
+            }
+            let total_deposit = Self::credential_deposit() * BalanceOf::<T>::sa(offers.len() as u64);
+            ensure!(
+                <T as Trait>::Currency::can_reserve(&sender, total_deposit),
+                "Insufficient balance for credential deposit."
+            );
+
+            for (to, cred) in offers {
+                Self::offer_credential(to, cred)?;
+            }
+        }
+
+        /// Accept a pending credential offer.
+        /// Only the offered holder can call this function.
+        pub fn accept_credential(origin, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            let offer = Self::credential_offers((sender.clone(), subject));
+            ensure!(offer.is_some(), "No credential offer.");
+
+            let offer = offer.expect("checked above; qed");
+            ensure!(<timestamp::Module<T>>::get() < offer.expires, "Credential offer expired.");
+            ensure!(!<RetiredSubjects<T>>::exists(subject), "Subject retired.");
+            // The issuer may have been removed since making the offer.
+            ensure!(Self::is_issuer(subject, &offer.credential.by), "Unauthorized.");
+
+            let deposit = Self::offer_deposits((sender.clone(), subject));
+            Self::insert_credential(sender.clone(), offer.credential, deposit)?;
+            <CredentialOffers<T>>::remove((sender.clone(), subject));
+            <OfferDeposits<T>>::remove((sender, subject));
+        }
+
+        /// Reject a pending credential offer, expired or not.
+        /// Only the offered holder can call this function.
+        pub fn reject_credential(origin, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            let offer = Self::credential_offers((sender.clone(), subject));
+            ensure!(offer.is_some(), "No credential offer.");
+
+            let offer = offer.expect("checked above; qed");
+            Self::remove_offer(&sender, subject);
+            Self::deposit_event(RawEvent::CredentialOfferRejected(sender, subject, offer.credential.by));
+        }
+
+        /// Withdraw a pending credential offer, expired or not.
+        /// Only the issuer who made the offer or the subject owner can call this function.
+        pub fn withdraw_offer(origin, holder: T::AccountId, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            let offer = Self::credential_offers((holder.clone(), subject)).ok_or("No credential offer.")?;
+            ensure!(
+                offer.credential.by == sender || Self::subject_owner(subject) == Some(sender.clone()),
+                "Unauthorized."
+            );
+
+            Self::remove_offer(&holder, subject);
+            Self::deposit_event(RawEvent::CredentialOfferWithdrawn(holder, subject, sender));
+        }
+
+        /// Clear a credential offer that can no longer be accepted,
+        /// as it expired or its subject was retired.
+        /// Anyone can call this function.
+        pub fn clear_expired_offer(origin, holder: T::AccountId, subject: Subject) {
+            let _sender = ensure_signed(origin)?;
+            let offer = Self::credential_offers((holder.clone(), subject)).ok_or("No credential offer.")?;
+            ensure!(
+                <timestamp::Module<T>>::get() >= offer.expires || <RetiredSubjects<T>>::exists(subject),
+                "Credential offer not expired."
+            );
+
+            Self::remove_offer(&holder, subject);
+            Self::deposit_event(RawEvent::CredentialOfferCleared(holder, subject, offer.credential.by));
+        }
 
+        /// Request a credential from the issuers of a subject,
+        /// backed by off-chain evidence with the given hash.
+        /// Reserves the request deposit from the holder until the
+        /// request is approved, denied or cancelled.
+        pub fn request_credential(origin, subject: Subject, evidence_hash: T::Hash) {
             let sender = ensure_signed(origin)?;
-            let subject_issuer = Self::subjects(subject);
-            ensure!(subject_issuer == sender, "Unauthorized.");
+            ensure!(<Subjects<T>>::exists(subject), "Subject does not exist.");
+            ensure!(!<RetiredSubjects<T>>::exists(subject), "Subject retired.");
+            ensure!(!<CredentialRequests<T>>::exists((sender.clone(), subject)), "Credential already requested.");
 
-            let now = <timestamp::Module<T>>::get();
-            let cred = Credential {
-              subject,
-              when: now,
-              by: sender.clone()
+            let owner = Self::subjects(subject);
+            let mut requests = Self::issuer_requests(&owner);
+            ensure!(requests.len() < MAX_OPEN_REQUESTS, "Too many open requests.");
+
+            let deposit = Self::request_deposit();
+            <T as Trait>::Currency::reserve(&sender, deposit)
+                .map_err(|_| "Insufficient balance for request deposit.")?;
+            <RequestDeposits<T>>::insert((sender.clone(), subject), (sender.clone(), deposit));
+
+            let request = CredentialRequest {
+                evidence_hash,
+                when: <timestamp::Module<T>>::get(),
             };
+            <CredentialRequests<T>>::insert((sender.clone(), subject), request);
+            requests.push((sender.clone(), subject));
+            <IssuerRequests<T>>::insert(&owner, requests);
+
+            Self::deposit_event(RawEvent::CredentialRequested(sender, subject, owner, evidence_hash));
+        }
+
+        /// Approve a credential request, issuing the credential right away
+        /// as the holder has already consented by requesting it.
+        /// Only an issuer can call this function.
+        pub fn approve_request(
+            origin,
+            holder: T::AccountId,
+            subject: Subject,
+            valid_from: Option<T::Moment>,
+            valid_until: Option<T::Moment>,
+            claims: Vec<Claim>,
+            document_hash: Option<H256>,
+            claims_root: Option<H256>
+        ) {
+            let sender = ensure_signed(origin)?;
+            ensure!(<CredentialRequests<T>>::exists((holder.clone(), subject)), "No credential request.");
+            let cred = Self::new_credential(&sender, subject, valid_from, valid_until, claims, document_hash, claims_root)?;
+            let deposit = Self::credential_deposit();
+            ensure!(
+                <T as Trait>::Currency::can_reserve(&sender, deposit),
+                "Insufficient balance for credential deposit."
+            );
+
+            Self::insert_credential(holder.clone(), cred, None)?;
+            <T as Trait>::Currency::reserve(&sender, deposit)?;
+            <CredentialDeposits<T>>::insert((holder.clone(), subject), (sender, deposit));
+            Self::remove_request(&holder, subject);
+        }
+
+        /// Deny a credential request.
+        /// Only an issuer can call this function.
+        pub fn deny_request(origin, holder: T::AccountId, subject: Subject, reason: ReasonCode) {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::is_issuer(subject, &sender), "Unauthorized.");
+            ensure!(<CredentialRequests<T>>::exists((holder.clone(), subject)), "No credential request.");
 
-            <Credentials<T>>::insert((to.clone(), subject), cred);
+            Self::remove_request(&holder, subject);
+            Self::deposit_event(RawEvent::CredentialRequestDenied(holder, subject, sender, reason));
+        }
+
+        /// Cancel a pending credential request, releasing its deposit.
+        /// Only the requesting holder can call this function.
+        pub fn cancel_request(origin, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            ensure!(<CredentialRequests<T>>::exists((sender.clone(), subject)), "No credential request.");
 
-            Self::deposit_event(RawEvent::CredentialIssued(to, subject, sender));
+            Self::remove_request(&sender, subject);
+            Self::deposit_event(RawEvent::CredentialRequestCancelled(sender, subject));
         }
 
         /// Revoke a credential.
         /// Only an issuer can call this function. 
-        pub fn revoke_credential(origin, to: T::AccountId, subject: Subject) {
+        pub fn revoke_credential(origin, to: T::AccountId, subject: Subject, reason: ReasonCode) {
             // Check if origin is an issuer.
             // Check if credential is issued.
-            // Change the bool flag of the stored credential tuple to false.
+            // Record the revocation, keeping the credential itself.
+
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::is_issuer(subject, &sender), "Unauthorized.");
+            Self::ensure_revocable(&to, subject)?;
+
+            Self::revoke(to, subject, sender, reason);
+        }
+
+        /// Revoke the credentials of many holders for a subject at once.
+        /// Either all credentials are revoked or none is.
+        /// Only an issuer can call this function.
+        pub fn revoke_credentials_batch(origin, subject: Subject, holders: Vec<T::AccountId>, reason: ReasonCode) {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::is_issuer(subject, &sender), "Unauthorized.");
+            ensure!(holders.len() <= MAX_BATCH_SIZE, "Batch too large.");
+
+            // Check every credential before revoking any.
+            for (i, holder) in holders.iter().enumerate() {
+                ensure!(!holders[..i].contains(holder), "Duplicate holder in batch.");
+                Self::ensure_revocable(holder, subject)?;
+            }
+
+            for holder in holders {
+                Self::revoke(holder, subject, sender.clone(), reason);
+            }
+        }
+
+        /// Offer to transfer a credential for a transferable subject to another
+        /// holder, e.g. a resold ticket, replacing any pending transfer of it.
+        /// The credential only moves once the recipient accepts the transfer.
+        /// Only the holder of a valid credential can call this function.
+        pub fn transfer_credential(origin, subject: Subject, to: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            Self::migrate_credential(sender.clone(), subject);
+            Self::ensure_transferable(&sender, &to, subject)?;
+
+            <PendingTransfers<T>>::insert((sender.clone(), subject), to.clone());
+            Self::deposit_event(RawEvent::CredentialTransferOffered(sender, to, subject));
+        }
+
+        /// Accept a pending credential transfer. The credential keeps its issuer
+        /// and issuance time, and its deposit stays reserved from the issuer.
+        /// Only the recipient of the transfer can call this function.
+        pub fn accept_transfer(origin, from: T::AccountId, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::pending_transfer((from.clone(), subject)) == Some(sender.clone()), "No credential transfer.");
+            Self::migrate_credential(from.clone(), subject);
+            Self::ensure_transferable(&from, &sender, subject)?;
+
+            Self::move_credential(&from, &sender, subject)?;
+            Self::deposit_event(RawEvent::CredentialTransferred(from, sender, subject));
+        }
+
+        /// Claim the credentials held through the sender's DID by its previous
+        /// controller, e.g. after rotating the controller key with `set_did_controller`.
+        /// Credentials keep their issuer, claims and status.
+        pub fn claim_did_credentials(origin, from: T::AccountId, subjects: Vec<Subject>) {
+            let sender = ensure_signed(origin)?;
+            let did = <did::Module<T>>::did_of(&sender).ok_or("Account controls no DID.")?;
+            ensure!(from != sender, "Cannot claim credentials from their holder.");
+            ensure!(subjects.len() <= MAX_BATCH_SIZE, "Batch too large.");
+
+            // Check every credential before moving any.
+            for (i, subject) in subjects.iter().enumerate() {
+                ensure!(!subjects[..i].contains(subject), "Duplicate subject in batch.");
+                let key = (from.clone(), *subject);
+                ensure!(<Credentials<T>>::exists(&key), "Credential not issued yet.");
+                ensure!(!<Revocations<T>>::exists(&key), "Credential revoked.");
+                ensure!(Self::credentials(&key).holder_did == Some(did), "Credential not held through the DID.");
+                let recipient_key = (sender.clone(), *subject);
+                ensure!(
+                    !<Credentials<T>>::exists(&recipient_key) || <Revocations<T>>::exists(&recipient_key),
+                    "Recipient already holds a credential for the subject."
+                );
+            }
+
+            for subject in subjects {
+                Self::move_credential(&from, &sender, subject)?;
+                Self::deposit_event(RawEvent::CredentialTransferred(from.clone(), sender.clone(), subject));
+            }
+        }
+
+        /// Cancel a pending credential transfer.
+        /// Only the holder or the recipient can call this function.
+        pub fn cancel_transfer(origin, holder: T::AccountId, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            let to = Self::pending_transfer((holder.clone(), subject)).ok_or("No credential transfer.")?;
+            ensure!(sender == holder || sender == to, "Unauthorized.");
+
+            <PendingTransfers<T>>::remove((holder.clone(), subject));
+            Self::deposit_event(RawEvent::CredentialTransferCancelled(holder, to, subject));
+        }
+
+        /// Temporarily suspend a credential, e.g. during an investigation.
+        /// Only an issuer can call this function.
+        pub fn suspend_credential(origin, holder: T::AccountId, subject: Subject) {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::is_issuer(subject, &sender), "Unauthorized.");
+            Self::migrate_credential(holder.clone(), subject);
+            ensure!(<Credentials<T>>::exists((holder.clone(), subject)), "Credential not issued yet.");
+            ensure!(!<Revocations<T>>::exists((holder.clone(), subject)), "Credential revoked.");
+            ensure!(!Self::credentials((holder.clone(), subject)).suspended, "Credential already suspended.");
+
+            <Credentials<T>>::mutate((holder.clone(), subject), |cred| cred.suspended = true);
+            Self::deposit_event(RawEvent::CredentialSuspended(holder, subject, sender));
+        }
 
+        /// Reinstate a suspended credential, keeping its original issuance data.
+        /// Only an issuer can call this function.
+        pub fn reinstate_credential(origin, holder: T::AccountId, subject: Subject) {
             let sender = ensure_signed(origin)?;
-            let subject_issuer = Self::subjects(subject);
-            ensure!(subject_issuer == sender, "Unauthorized.");
-            ensure!(<Credentials<T>>::exists((to.clone(), subject)), "Credential not issued yet.");
+            ensure!(Self::is_issuer(subject, &sender), "Unauthorized.");
+            Self::migrate_credential(holder.clone(), subject);
+            ensure!(<Credentials<T>>::exists((holder.clone(), subject)), "Credential not issued yet.");
+            ensure!(!<Revocations<T>>::exists((holder.clone(), subject)), "Credential revoked.");
+            ensure!(Self::credentials((holder.clone(), subject)).suspended, "Credential not suspended.");
 
-            <Credentials<T>>::remove((to.clone(), subject));
-            Self::deposit_event(RawEvent::CredentialRevoked(to, subject, sender));
+            <Credentials<T>>::mutate((holder.clone(), subject), |cred| cred.suspended = false);
+            Self::deposit_event(RawEvent::CredentialReinstated(holder, subject, sender));
         }
 
         /// Verify a credential.
@@ -92,17 +809,36 @@ decl_module! {
             let _sender = ensure_signed(origin)?;
 
             // Ensure credential is issued and allowed to be verified.
-            ensure!(<Credentials<T>>::exists((holder.clone(), subject)), "Credential not issued yet.");
+            Self::check_credential(&holder, subject)?;
+        }
+
+        /// Verify a credential along with the hash of the off-chain
+        /// document presented for it.
+        pub fn verify_document_hash(origin, holder: T::AccountId, subject: Subject, document_hash: H256) {
+            let _sender = ensure_signed(origin)?;
+
+            Self::check_credential(&holder, subject)?;
+            Self::check_document_hash(&holder, subject, &document_hash)?;
         }
 
         /// Create a new subject.
-        pub fn create_subject(origin) {
-            let sender = ensure_signed(origin)?;
+        /// Credentials for it may only carry claims matching `schema`.
+        pub fn create_subject(origin, name: Vec<u8>, description: Vec<u8>, schema: Vec<SchemaField>) {
+            let sender = T::CreateSubjectOrigin::ensure_creator(origin)?;
             let subject_count = <SubjectCount<T>>::get();
 
             ensure!(subject_count < MAX_SUBJECT, "Max issuance count reached");
+            ensure!(name.len() <= MAX_SUBJECT_NAME_LEN, "Subject name too long.");
+            ensure!(description.len() <= MAX_SUBJECT_DESCRIPTION_LEN, "Subject description too long.");
+            Self::check_schema(&schema)?;
+
+            let deposit = Self::subject_deposit();
+            <T as Trait>::Currency::reserve(&sender, deposit)
+                .map_err(|_| "Insufficient balance for subject deposit.")?;
+            <SubjectDeposits<T>>::insert(subject_count, (sender.clone(), deposit));
 
             <Subjects<T>>::insert(subject_count, sender.clone());
+            <SubjectInfos<T>>::insert(subject_count, SubjectInfo { name, description, schema });
 
             // Update the subject nonce.
             <SubjectCount<T>>::put(subject_count + 1);
@@ -110,117 +846,2017 @@ decl_module! {
             // Deposit the event.
             Self::deposit_event(RawEvent::SubjectCreated(sender, subject_count));
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+        /// Allow an account to create subjects under `AllowlistCreation`.
+        /// Only the root origin can call this function.
+        pub fn add_subject_creator(origin, who: T::AccountId) {
+            ensure_root(origin)?;
+            ensure!(!Self::subject_creators(&who), "Already a subject creator.");
 
-  use primitives::{Blake2Hasher, H256};
-  use runtime_io::with_externalities;
-  use runtime_primitives::{
-    testing::{Digest, DigestItem, Header},
-    traits::{BlakeTwo256, IdentityLookup},
-    BuildStorage,
-  };
-  use support::{assert_noop, assert_ok, impl_outer_origin};
+            <SubjectCreators<T>>::insert(&who, true);
+            Self::deposit_event(RawEvent::SubjectCreatorAdded(who));
+        }
 
-  impl_outer_origin! {
-    pub enum Origin for Test {}
-  }
+        /// Remove an account from the subject creator allowlist.
+        /// Subjects it already created are unaffected.
+        /// Only the root origin can call this function.
+        pub fn remove_subject_creator(origin, who: T::AccountId) {
+            ensure_root(origin)?;
+            ensure!(Self::subject_creators(&who), "Not a subject creator.");
 
-  // For testing the module, we construct a mock runtime. This means
-  // first constructing a configuration type (`Test`) which implements each of the
-  // configuration traits of modules we use.
-  #[derive(Clone, Eq, PartialEq)]
-  pub struct Test;
-  impl system::Trait for Test {
-    type Origin = Origin;
-    type Index = u64;
-    type BlockNumber = u64;
-    type Hash = H256;
-    type Hashing = BlakeTwo256;
-    type Digest = Digest;
-    type AccountId = u64;
-    type Lookup = IdentityLookup<u64>;
-    type Header = Header;
-    type Event = ();
-    type Log = DigestItem;
-  }
-  impl timestamp::Trait for Test {
-    type Moment = u64;
-    type OnTimestampSet = ();
-  }
-  impl Trait for Test {
-    type Event = ();
-  }
-  type VerifiableCreds = Module<Test>;
+            <SubjectCreators<T>>::remove(&who);
+            Self::deposit_event(RawEvent::SubjectCreatorRemoved(who));
+        }
 
-  // builds the genesis config store and sets mock values
-  fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-    let mut t = system::GenesisConfig::<Test>::default()
-      .build_storage()
-      .unwrap()
-      .0;
-    t.extend(
-      GenesisConfig::<Test> {
-        issuers: vec![(1, 1), (2, 2)],
-        subject_count: 3,
-      }
-      .build_storage()
-      .unwrap()
-      .0,
-    );
-    t.into()
-  }
+        /// Require holders to hold valid credentials for `prerequisites` before
+        /// being issued a credential for the subject, replacing any previous ones.
+        /// `on_revoke` decides what happens to issued credentials when a
+        /// prerequisite credential of their holder is revoked.
+        /// Only the subject owner can call this function.
+        pub fn set_subject_prerequisites(
+            origin,
+            subject: Subject,
+            prerequisites: Vec<Subject>,
+            on_revoke: PrerequisiteRevocation
+        ) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
+            ensure!(prerequisites.len() <= MAX_PREREQUISITES, "Too many prerequisites.");
+            for (i, prerequisite) in prerequisites.iter().enumerate() {
+                ensure!(<Subjects<T>>::exists(prerequisite), "Subject does not exist.");
+                ensure!(!prerequisites[..i].contains(prerequisite), "Duplicate prerequisite.");
+                ensure!(!Self::requires(*prerequisite, subject), "Prerequisite cycle.");
+                let dependents = Self::dependent_subjects(prerequisite);
+                ensure!(
+                    dependents.contains(&subject) || dependents.len() < MAX_DEPENDENT_SUBJECTS,
+                    "Too many dependent subjects."
+                );
+            }
 
-  #[test]
-  fn should_fail_issue() {
-    with_externalities(&mut new_test_ext(), || {
-        assert_noop!(
-            VerifiableCreds::issue_credential(Origin::signed(1), 3, 2),
-            "Unauthorized.");
-    });
-  }
+            for previous in Self::subject_prerequisites(subject).subjects {
+                <DependentSubjects<T>>::mutate(previous, |dependents| dependents.retain(|d| *d != subject));
+            }
+            for prerequisite in &prerequisites {
+                <DependentSubjects<T>>::mutate(prerequisite, |dependents| dependents.push(subject));
+            }
+            let info = Prerequisites {
+                subjects: prerequisites.clone(),
+                on_revoke,
+            };
+            <SubjectPrerequisites<T>>::insert(subject, info);
 
-  #[test]
-  fn should_issue() {
-    with_externalities(&mut new_test_ext(), || {
-        assert_ok!(
-            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1));
-    });
-  }
+            Self::deposit_event(RawEvent::SubjectPrerequisitesSet(subject, prerequisites));
+        }
 
-  #[test]
-  fn should_revoke() {
-    with_externalities(&mut new_test_ext(), || {
-        assert_ok!(
-            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1));
-        assert_ok!(
-            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1));
-    });
-  }
+        /// Allow or forbid holders to transfer their credentials for a subject,
+        /// for bearer-style entitlements such as tickets or memberships.
+        /// Only the subject owner can call this function.
+        pub fn set_subject_transferable(origin, subject: Subject, transferable: bool) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
 
-  #[test]
-  fn should_add_subject() {
-    with_externalities(&mut new_test_ext(), || {
-        assert_ok!(
-            VerifiableCreds::create_subject(Origin::signed(3)));
-        assert_eq!(
-            VerifiableCreds::issuers(3), 3);
+            <TransferableSubjects<T>>::insert(subject, transferable);
+            Self::deposit_event(RawEvent::SubjectTransferabilitySet(subject, transferable));
+        }
+
+        /// Transfer ownership of a subject.
+        /// Only the subject owner can call this function.
+        pub fn transfer_subject(origin, subject: Subject, new_owner: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
+            ensure!(new_owner != sender, "Already the subject owner.");
+
+            // Hand the open requests for this subject over to the new owner.
+            let (moved, kept): (Vec<_>, Vec<_>) = Self::issuer_requests(&sender)
+                .into_iter()
+                .partition(|(_, s)| *s == subject);
+            let mut requests = Self::issuer_requests(&new_owner);
+            ensure!(
+                requests.len() + moved.len() <= MAX_OPEN_REQUESTS,
+                "Too many open requests."
+            );
+            if !moved.is_empty() {
+                requests.extend(moved);
+                <IssuerRequests<T>>::insert(&sender, kept);
+                <IssuerRequests<T>>::insert(&new_owner, requests);
+            }
+
+            <Subjects<T>>::insert(subject, new_owner.clone());
+            Self::deposit_event(RawEvent::SubjectTransferred(subject, sender, new_owner));
+        }
+
+        /// Authorize an additional issuer for a subject.
+        /// Only the subject owner can call this function.
+        pub fn add_subject_issuer(origin, subject: Subject, issuer: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
+
+            let mut issuers = Self::subject_issuers(subject);
+            ensure!(!issuers.contains(&issuer), "Already an issuer.");
+            ensure!(issuers.len() < MAX_SUBJECT_ISSUERS, "Too many issuers.");
+
+            issuers.push(issuer.clone());
+            <SubjectIssuers<T>>::insert(subject, issuers);
+            Self::deposit_event(RawEvent::SubjectIssuerAdded(subject, issuer));
+        }
+
+        /// Remove an additional issuer from a subject.
+        /// Only the subject owner can call this function.
+        pub fn remove_subject_issuer(origin, subject: Subject, issuer: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
+
+            let mut issuers = Self::subject_issuers(subject);
+            ensure!(issuers.contains(&issuer), "Not an issuer.");
+
+            issuers.retain(|i| *i != issuer);
+            <SubjectIssuers<T>>::insert(subject, issuers);
+            Self::deposit_event(RawEvent::SubjectIssuerRemoved(subject, issuer));
+        }
+
+        /// Retire a subject, blocking new issuance and releasing its deposit.
+        /// Open requests for the subject are dropped, releasing their deposits.
+        /// Existing credentials are invalidated if `invalidate` is set and
+        /// are kept as historical records otherwise. Invalidated credentials
+        /// are dropped from the indexes and their deposits released, a batch
+        /// at a time, the rest through `release_retired_credentials`.
+        /// Only the subject owner can call this function.
+        pub fn retire_subject(origin, subject: Subject, invalidate: bool) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
+            ensure!(!<RetiredSubjects<T>>::exists(subject), "Subject retired.");
+
+            let retirement = Retirement {
+                when: <timestamp::Module<T>>::get(),
+                invalidated: invalidate,
+            };
+            <RetiredSubjects<T>>::insert(subject, retirement);
+            Self::release_deposit(<SubjectDeposits<T>>::take(subject));
+
+            // Open requests are bounded by `MAX_OPEN_REQUESTS` per owner.
+            let (dropped, kept): (Vec<_>, Vec<_>) = Self::issuer_requests(&sender)
+                .into_iter()
+                .partition(|(_, s)| *s == subject);
+            if !dropped.is_empty() {
+                <IssuerRequests<T>>::insert(&sender, kept);
+                for key in dropped {
+                    <CredentialRequests<T>>::remove(&key);
+                    Self::release_deposit(<RequestDeposits<T>>::take(&key));
+                }
+            }
+            if invalidate {
+                Self::release_invalidated(subject);
+            }
+
+            Self::deposit_event(RawEvent::SubjectRetired(subject, invalidate));
+        }
+
+        /// Release the next batch of credentials invalidated by the retirement
+        /// of their subject, dropping them from the indexes and unreserving
+        /// their deposits.
+        /// Anyone can call this function.
+        pub fn release_retired_credentials(origin, subject: Subject) {
+            let _sender = ensure_signed(origin)?;
+            ensure!(
+                Self::retired_subjects(subject).map_or(false, |r| r.invalidated),
+                "Subject not retired with invalidation."
+            );
+            ensure!(Self::subject_holders_count(subject) > 0, "No credentials left to release.");
+
+            Self::release_invalidated(subject);
+        }
+
+        /// Set the category of a subject, against which the accreditation
+        /// of its issuers is checked.
+        /// Only the subject owner can call this function.
+        pub fn set_subject_category(origin, subject: Subject, category: Category) {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_subject_owner(subject, &sender)?;
+
+            <SubjectCategories<T>>::insert(subject, category);
+            Self::deposit_event(RawEvent::SubjectCategorized(subject, category));
+        }
+
+        /// Accredit an issuer for a category on behalf of the root authority.
+        /// An existing accreditation must be removed with `remove_accreditation` first.
+        /// Only the root origin can call this function.
+        pub fn accredit_issuer(origin, issuer: T::AccountId, category: Category) {
+            ensure_root(origin)?;
+            ensure!(!<Accreditations<T>>::exists((issuer.clone(), category)), "Issuer already accredited.");
+
+            let accreditation = Accreditation { by: None, depth: 1 };
+            <Accreditations<T>>::insert((issuer.clone(), category), accreditation);
+            Self::deposit_event(RawEvent::IssuerAccredited(issuer, category, None));
+        }
+
+        /// Accredit a sub-issuer for a category.
+        /// Only an issuer accredited for the category through an unbroken chain
+        /// and below the depth limit can call this function.
+        pub fn accredit_sub_issuer(origin, issuer: T::AccountId, category: Category) {
+            let sender = ensure_signed(origin)?;
+            // Never overwrite an accreditation, which would let sub-issuers
+            // cut off the issuers above them.
+            ensure!(!<Accreditations<T>>::exists((issuer.clone(), category)), "Issuer already accredited.");
+            let chain = Self::accreditation_chain(&sender, category).ok_or("Unauthorized.")?;
+            let depth = chain.len() as u32 + 1;
+            ensure!(depth <= Self::max_accreditation_depth(), "Accreditation depth limit reached.");
+
+            let accreditation = Accreditation { by: Some(sender.clone()), depth };
+            <Accreditations<T>>::insert((issuer.clone(), category), accreditation);
+            Self::deposit_event(RawEvent::IssuerAccredited(issuer, category, Some(sender)));
+        }
+
+        /// Withdraw an accreditation, breaking the chain of every sub-issuer below it.
+        /// Only the accrediting issuer can call this function.
+        pub fn revoke_accreditation(origin, issuer: T::AccountId, category: Category) {
+            let sender = ensure_signed(origin)?;
+            let accreditation = Self::accreditations((issuer.clone(), category)).ok_or("Issuer not accredited.")?;
+            ensure!(accreditation.by == Some(sender), "Unauthorized.");
+
+            <Accreditations<T>>::remove((issuer.clone(), category));
+            Self::deposit_event(RawEvent::AccreditationRevoked(issuer, category));
+        }
+
+        /// Withdraw any accreditation on behalf of the root authority.
+        /// Only the root origin can call this function.
+        pub fn remove_accreditation(origin, issuer: T::AccountId, category: Category) {
+            ensure_root(origin)?;
+            ensure!(<Accreditations<T>>::exists((issuer.clone(), category)), "Issuer not accredited.");
+
+            <Accreditations<T>>::remove((issuer.clone(), category));
+            Self::deposit_event(RawEvent::AccreditationRevoked(issuer, category));
+        }
+
+        /// Create a status list of `size` entries for credentials of a subject
+        /// signed off-chain. All entries start out unrevoked.
+        /// Reserves a deposit proportional to the size of the list from its creator.
+        /// Only an issuer can call this function.
+        pub fn create_status_list(origin, subject: Subject, size: u32) {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::is_issuer(subject, &sender), "Unauthorized.");
+            ensure!(size > 0 && size <= MAX_STATUS_LIST_SIZE, "Invalid status list size.");
+
+            let id = Self::status_list_count();
+            let next_id = id.checked_add(1).ok_or("Overflow creating a new status list.")?;
+
+            let bytes = (size + 7) / 8;
+            let deposit = Self::status_list_byte_deposit() * BalanceOf::<T>::sa(bytes as u64);
+            <T as Trait>::Currency::reserve(&sender, deposit)
+                .map_err(|_| "Insufficient balance for status list deposit.")?;
+            <StatusListDeposits<T>>::insert(id, (sender.clone(), deposit));
+
+            let list = StatusList {
+                subject,
+                owner: sender.clone(),
+                size,
+                bits: vec![0; bytes as usize],
+                updated: <timestamp::Module<T>>::get(),
+            };
+            <StatusLists<T>>::insert(id, list);
+            <StatusListCount<T>>::put(next_id);
+
+            Self::deposit_event(RawEvent::StatusListCreated(id, subject, sender));
+        }
+
+        /// Mark the entries at `indices` of a status list as revoked,
+        /// or as unrevoked if `revoked` is not set.
+        /// Only the owner of the list can call this function,
+        /// as long as it is still an issuer of the subject.
+        pub fn set_status_bits(origin, list: StatusListId, indices: Vec<u32>, revoked: bool) {
+            let sender = ensure_signed(origin)?;
+            let mut status_list = Self::status_lists(list).ok_or("Status list does not exist.")?;
+            ensure!(status_list.owner == sender, "Unauthorized.");
+            ensure!(Self::is_issuer(status_list.subject, &sender), "Unauthorized.");
+            ensure!(indices.len() <= MAX_STATUS_UPDATES, "Too many status updates.");
+            ensure!(indices.iter().all(|i| *i < status_list.size), "Status index out of range.");
+
+            for index in &indices {
+                let (byte, mask) = ((index / 8) as usize, 0x80u8 >> (index % 8));
+                if revoked {
+                    status_list.bits[byte] |= mask;
+                } else {
+                    status_list.bits[byte] &= !mask;
+                }
+            }
+            status_list.updated = <timestamp::Module<T>>::get();
+            <StatusLists<T>>::insert(list, status_list);
+
+            Self::deposit_event(RawEvent::StatusListUpdated(list, indices, revoked));
+        }
+
+        /// Remove a status list that is no longer used, releasing its deposit.
+        /// Credentials referring to the list can no longer be checked against it.
+        /// Only the owner of the list or the subject owner can call this function.
+        pub fn remove_status_list(origin, list: StatusListId) {
+            let sender = ensure_signed(origin)?;
+            let status_list = Self::status_lists(list).ok_or("Status list does not exist.")?;
+            ensure!(
+                status_list.owner == sender || Self::subject_owner(status_list.subject) == Some(sender.clone()),
+                "Unauthorized."
+            );
+
+            <StatusLists<T>>::remove(list);
+            Self::release_deposit(<StatusListDeposits<T>>::take(list));
+            Self::deposit_event(RawEvent::StatusListRemoved(list, sender));
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The credential issued to `holder` for a subject, if any.
+    pub fn issued_credential(holder: &T::AccountId, subject: Subject) -> Option<Credential<T::Moment, T::AccountId>> {
+        let key = (holder.clone(), subject);
+        if <Credentials<T>>::exists(&key) {
+            Some(Self::credentials(key))
+        } else {
+            None
+        }
+    }
+
+    /// A page of the subjects `holder` holds unrevoked credentials for,
+    /// starting at index `start` and holding at most `limit` entries.
+    pub fn holder_subjects(holder: &T::AccountId, start: u32, limit: u32) -> Vec<Subject> {
+        let end = Self::page_end(Self::holder_subjects_count(holder), start, limit);
+        (start..end)
+            .map(|i| Self::holder_subject_by_index((holder.clone(), i)))
+            .collect()
+    }
+
+    /// A page of the holders of unrevoked credentials for a subject,
+    /// starting at index `start` and holding at most `limit` entries.
+    pub fn subject_holders(subject: Subject, start: u32, limit: u32) -> Vec<T::AccountId> {
+        let end = Self::page_end(Self::subject_holders_count(subject), start, limit);
+        (start..end)
+            .map(|i| Self::subject_holder_by_index((subject, i)))
+            .collect()
+    }
+
+    /// Migrate a batch of the most recently queued credentials, bumping the
+    /// storage version once all credentials are queued and migrated.
+    fn migrate_credentials() {
+        let count = Self::pending_migration_count();
+        let first = count.saturating_sub(MAX_MIGRATIONS_PER_BLOCK as u32);
+        for i in first..count {
+            let (holder, subject) = <PendingMigrations<T>>::take(i);
+            Self::migrate_credential(holder, subject);
+        }
+
+        if first == 0 && Self::migrations_queued() {
+            <PendingMigrationCount<T>>::kill();
+            <MigrationsQueued<T>>::kill();
+            <StorageVersion<T>>::put(STORAGE_VERSION);
+            Self::deposit_event(RawEvent::StorageMigrated(STORAGE_VERSION));
+        } else {
+            <PendingMigrationCount<T>>::put(first);
+        }
+    }
+
+    /// Rewrite a credential stored as `CredentialV0` in the current layout and index it
+    /// unless revoked. Called before changing a credential while the storage is outdated,
+    /// so that it is never read or written in the wrong layout.
+    /// Missing entries and entries already in the current layout are left untouched.
+    fn migrate_credential(holder: T::AccountId, subject: Subject) {
+        if Self::storage_version() >= STORAGE_VERSION {
+            return;
+        }
+        let key = (holder, subject);
+        // Map values are stored under the twox-128 hash of their `key_for` key.
+        let raw = match runtime_io::storage(&runtime_io::twox_128(&<Credentials<T>>::key_for(&key))) {
+            Some(raw) => raw,
+            None => return,
+        };
+        if decode_exact::<Credential<T::Moment, T::AccountId>>(&raw).is_some() {
+            return;
+        }
+        let legacy = match decode_exact::<CredentialV0<T::Moment, T::AccountId>>(&raw) {
+            Some(legacy) => legacy,
+            None => return,
+        };
+        if !<Revocations<T>>::exists(&key) && Self::index_credential(&key.0, subject).is_err() {
+            return;
+        }
+
+        let cred = Credential {
+            subject: legacy.subject,
+            when: legacy.when.clone(),
+            by: legacy.by,
+            valid_from: legacy.when,
+            valid_until: None,
+            claims: Vec::new(),
+            suspended: false,
+            document_hash: None,
+            claims_root: None,
+            issuer_did: None,
+            holder_did: None
+        };
+        <Credentials<T>>::insert(&key, cred);
+    }
+
+    /// Whether the entry at `index` of a status list is revoked,
+    /// or `None` if there is no such entry.
+    pub fn status_revoked(list: StatusListId, index: u32) -> Option<bool> {
+        let status_list = Self::status_lists(list)?;
+        if index >= status_list.size {
+            return None;
+        }
+        Some(status_list.bits[(index / 8) as usize] & (0x80 >> (index % 8)) != 0)
+    }
+
+    /// The chain of issuers accrediting `issuer` for a category, starting with
+    /// the issuer accredited by the root authority and ending with `issuer`,
+    /// or `None` if `issuer` is not accredited through an unbroken chain.
+    pub fn accreditation_chain(issuer: &T::AccountId, category: Category) -> Option<Vec<T::AccountId>> {
+        let mut chain = vec![issuer.clone()];
+        let mut accreditation = Self::accreditations((issuer.clone(), category))?;
+        // Each link must be exactly one level closer to the root,
+        // so that the walk is bounded and cannot cycle.
+        while let Some(by) = accreditation.by {
+            let parent = Self::accreditations((by.clone(), category))?;
+            if parent.depth + 1 != accreditation.depth {
+                return None;
+            }
+            chain.push(by);
+            accreditation = parent;
+        }
+        if accreditation.depth != 1 {
+            return None;
+        }
+
+        chain.reverse();
+        Some(chain)
+    }
+
+    /// The accreditation chain of the issuer of the holder's credential
+    /// for the subject's category, if the credential exists and its issuer is accredited.
+    pub fn credential_accreditation(holder: &T::AccountId, subject: Subject) -> Option<Vec<T::AccountId>> {
+        let credential = Self::issued_credential(holder, subject)?;
+        let category = Self::subject_category(subject)?;
+        Self::accreditation_chain(&credential.by, category)
+    }
+
+    fn page_end(count: u32, start: u32, limit: u32) -> u32 {
+        start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count)
+    }
+
+    /// The owner of a subject, if the subject exists.
+    pub fn subject_owner(subject: Subject) -> Option<T::AccountId> {
+        if <Subjects<T>>::exists(subject) {
+            Some(Self::subjects(subject))
+        } else {
+            None
+        }
+    }
+
+    /// Whether `who` may issue and revoke credentials for a subject,
+    /// either as its owner or as an additional issuer.
+    pub fn is_issuer(subject: Subject, who: &T::AccountId) -> bool {
+        <Subjects<T>>::exists(subject)
+            && (Self::subjects(subject) == *who || Self::subject_issuers(subject).contains(who))
+    }
+
+    /// Build a new credential issued by `issuer`, checking that it may issue
+    /// for the subject and that the validity window and claims are sound.
+    fn new_credential(
+        issuer: &T::AccountId,
+        subject: Subject,
+        valid_from: Option<T::Moment>,
+        valid_until: Option<T::Moment>,
+        claims: Vec<Claim>,
+        document_hash: Option<H256>,
+        claims_root: Option<H256>
+    ) -> rstd::result::Result<Credential<T::Moment, T::AccountId>, &'static str> {
+        ensure!(Self::is_issuer(subject, issuer), "Unauthorized.");
+        ensure!(!<RetiredSubjects<T>>::exists(subject), "Subject retired.");
+
+        let now = <timestamp::Module<T>>::get();
+        let valid_from = valid_from.unwrap_or(now);
+        if let Some(valid_until) = valid_until {
+            ensure!(valid_from < valid_until, "Invalid validity window.");
+        }
+        Self::check_claims(subject, &claims)?;
+
+        Ok(Credential {
+            subject,
+            when: now,
+            by: issuer.clone(),
+            valid_from,
+            valid_until,
+            claims,
+            suspended: false,
+            document_hash,
+            claims_root,
+            issuer_did: <did::Module<T>>::did_of(issuer),
+            holder_did: None
+        })
+    }
+
+    /// Offer a credential to `to`, replacing any pending offer for the subject.
+    /// Reserves the credential deposit from the issuer, releasing the
+    /// deposit of the replaced offer.
+    fn offer_credential(to: T::AccountId, cred: Credential<T::Moment, T::AccountId>) -> Result {
+        let (subject, by) = (cred.subject, cred.by.clone());
+        let offer = CredentialOffer {
+            expires: cred.when.clone() + Self::offer_timeout(),
+            credential: cred,
+        };
+
+        let deposit = Self::credential_deposit();
+        <T as Trait>::Currency::reserve(&by, deposit)?;
+        Self::release_deposit(<OfferDeposits<T>>::take((to.clone(), subject)));
+        <OfferDeposits<T>>::insert((to.clone(), subject), (by.clone(), deposit));
+        <CredentialOffers<T>>::insert((to.clone(), subject), offer);
+
+        Self::deposit_event(RawEvent::CredentialOffered(to, subject, by));
+        Ok(())
+    }
+
+    /// Drop up to `MAX_BATCH_SIZE` credentials invalidated by the retirement of
+    /// their subject from the indexes, releasing their deposits.
+    /// The credentials themselves are kept as records.
+    fn release_invalidated(subject: Subject) {
+        let count = Self::subject_holders_count(subject);
+        let first = count.saturating_sub(MAX_BATCH_SIZE as u32);
+        // Take holders from the end, so that no entry is swapped into a freed slot.
+        for index in (first..count).rev() {
+            let holder = Self::subject_holder_by_index((subject, index));
+            Self::unindex_credential(&holder, subject);
+            Self::release_deposit(<CredentialDeposits<T>>::take((holder, subject)));
+        }
+    }
+
+    /// Remove a pending credential offer, releasing its deposit.
+    fn remove_offer(holder: &T::AccountId, subject: Subject) {
+        <CredentialOffers<T>>::remove((holder.clone(), subject));
+        Self::release_deposit(<OfferDeposits<T>>::take((holder.clone(), subject)));
+    }
+
+    /// Unreserve a deposit, if any.
+    fn release_deposit(deposit: Option<(T::AccountId, BalanceOf<T>)>) {
+        if let Some((who, amount)) = deposit {
+            <T as Trait>::Currency::unreserve(&who, amount);
+        }
+    }
+
+    fn ensure_revocable(holder: &T::AccountId, subject: Subject) -> Result {
+        ensure!(<Credentials<T>>::exists((holder.clone(), subject)), "Credential not issued yet.");
+        ensure!(!<Revocations<T>>::exists((holder.clone(), subject)), "Credential already revoked.");
+        Ok(())
+    }
+
+    /// Record the revocation of a credential, keeping the credential itself.
+    fn revoke(holder: T::AccountId, subject: Subject, by: T::AccountId, reason: ReasonCode) {
+        Self::migrate_credential(holder.clone(), subject);
+        let revocation = Revocation {
+            by: by.clone(),
+            when: <timestamp::Module<T>>::get(),
+            reason,
+        };
+        <Revocations<T>>::insert((holder.clone(), subject), revocation);
+        <PendingTransfers<T>>::remove((holder.clone(), subject));
+        Self::release_deposit(<CredentialDeposits<T>>::take((holder.clone(), subject)));
+        Self::unindex_credential(&holder, subject);
+        Self::deposit_event(RawEvent::CredentialRevoked(holder.clone(), subject, by.clone(), reason));
+
+        // Apply the revocation policy of the holder's credentials depending on this one.
+        // Cascading terminates as prerequisites are acyclic.
+        for dependent in Self::dependent_subjects(subject) {
+            let key = (holder.clone(), dependent);
+            if !<Credentials<T>>::exists(&key) || <Revocations<T>>::exists(&key) {
+                continue;
+            }
+
+            Self::migrate_credential(holder.clone(), dependent);
+            match Self::subject_prerequisites(dependent).on_revoke {
+                PrerequisiteRevocation::Cascade => Self::revoke(holder.clone(), dependent, by.clone(), reason),
+                PrerequisiteRevocation::FlagForReview => {
+                    <Credentials<T>>::mutate(&key, |cred| cred.suspended = true);
+                    Self::deposit_event(RawEvent::CredentialFlaggedForReview(holder.clone(), dependent, subject));
+                }
+            }
+        }
+    }
+
+    /// Check that the holder's credential for a subject may be transferred to `to`.
+    fn ensure_transferable(holder: &T::AccountId, to: &T::AccountId, subject: Subject) -> Result {
+        ensure!(Self::is_transferable(subject), "Credential not transferable.");
+        ensure!(to != holder, "Cannot transfer a credential to its holder.");
+        Self::check_credential(holder, subject)?;
+        let recipient_key = (to.clone(), subject);
+        ensure!(
+            !<Credentials<T>>::exists(&recipient_key) || <Revocations<T>>::exists(&recipient_key),
+            "Recipient already holds a credential for the subject."
+        );
+        Self::check_prerequisites(to, subject)?;
+        // Keep the credentials depending on this one backed by it.
+        for dependent in Self::dependent_subjects(subject) {
+            let key = (holder.clone(), dependent);
+            ensure!(
+                !<Credentials<T>>::exists(&key) || <Revocations<T>>::exists(&key),
+                "Credential is a prerequisite of another held credential."
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Move a credential, along with its deposit, from one holder to another,
+    /// referring to the new holder's DID.
+    fn move_credential(from: &T::AccountId, to: &T::AccountId, subject: Subject) -> Result {
+        let key = (to.clone(), subject);
+        Self::index_credential(to, subject)?;
+        Self::unindex_credential(from, subject);
+        <PendingTransfers<T>>::remove((from.clone(), subject));
+        let mut cred = <Credentials<T>>::take((from.clone(), subject));
+        cred.holder_did = <did::Module<T>>::did_of(to);
+        <Credentials<T>>::insert(&key, cred);
+        <Revocations<T>>::remove(&key);
+        if let Some(deposit) = <CredentialDeposits<T>>::take((from.clone(), subject)) {
+            <CredentialDeposits<T>>::insert(&key, deposit);
+        }
+
+        Ok(())
+    }
+
+    /// Remove a pending credential request and its entry in the owner's open requests,
+    /// releasing its deposit.
+    fn remove_request(holder: &T::AccountId, subject: Subject) {
+        <CredentialRequests<T>>::remove((holder.clone(), subject));
+        Self::release_deposit(<RequestDeposits<T>>::take((holder.clone(), subject)));
+        <IssuerRequests<T>>::mutate(Self::subjects(subject), |requests| {
+            requests.retain(|(h, s)| !(h == holder && *s == subject))
+        });
+    }
+
+    /// Store a credential the holder has consented to, along with its
+    /// already reserved deposit. The deposit of a replaced credential is released.
+    fn insert_credential(
+        holder: T::AccountId,
+        mut cred: Credential<T::Moment, T::AccountId>,
+        deposit: Option<(T::AccountId, BalanceOf<T>)>
+    ) -> Result {
+        let subject = cred.subject;
+        cred.holder_did = <did::Module<T>>::did_of(&holder);
+        let (by, claims) = (cred.by.clone(), cred.claims.clone());
+
+        Self::check_prerequisites(&holder, subject)?;
+        Self::index_credential(&holder, subject)?;
+        <Credentials<T>>::insert((holder.clone(), subject), cred);
+        <Revocations<T>>::remove((holder.clone(), subject));
+        Self::release_deposit(<CredentialDeposits<T>>::take((holder.clone(), subject)));
+        if let Some(deposit) = deposit {
+            <CredentialDeposits<T>>::insert((holder.clone(), subject), deposit);
+        }
+
+        Self::deposit_event(RawEvent::CredentialIssued(holder, subject, by, claims));
+        Ok(())
+    }
+
+    /// Add a credential to the holder and subject indexes, unless already indexed.
+    fn index_credential(holder: &T::AccountId, subject: Subject) -> Result {
+        let key = (holder.clone(), subject);
+        if <HolderSubjectsIndex<T>>::exists(&key) {
+            return Ok(());
+        }
+
+        let holder_count = Self::holder_subjects_count(holder);
+        let new_holder_count = holder_count.checked_add(1)
+            .ok_or("Overflow adding a credential to the holder index.")?;
+        let subject_count = Self::subject_holders_count(subject);
+        let new_subject_count = subject_count.checked_add(1)
+            .ok_or("Overflow adding a credential to the subject index.")?;
+
+        <HolderSubjectsArray<T>>::insert((holder.clone(), holder_count), subject);
+        <HolderSubjectsCount<T>>::insert(holder, new_holder_count);
+        <HolderSubjectsIndex<T>>::insert(&key, holder_count);
+
+        <SubjectHoldersArray<T>>::insert((subject, subject_count), holder.clone());
+        <SubjectHoldersCount<T>>::insert(subject, new_subject_count);
+        <SubjectHoldersIndex<T>>::insert(&key, subject_count);
+
+        Ok(())
+    }
+
+    /// Remove a credential from the holder and subject indexes,
+    /// swapping the last entry into the freed slot.
+    fn unindex_credential(holder: &T::AccountId, subject: Subject) {
+        let key = (holder.clone(), subject);
+        if !<HolderSubjectsIndex<T>>::exists(&key) {
+            return;
+        }
+
+        let index = <HolderSubjectsIndex<T>>::take(&key);
+        let last = Self::holder_subjects_count(holder) - 1;
+        if index != last {
+            let last_subject = Self::holder_subject_by_index((holder.clone(), last));
+            <HolderSubjectsArray<T>>::insert((holder.clone(), index), last_subject);
+            <HolderSubjectsIndex<T>>::insert((holder.clone(), last_subject), index);
+        }
+        <HolderSubjectsArray<T>>::remove((holder.clone(), last));
+        <HolderSubjectsCount<T>>::insert(holder, last);
+
+        let index = <SubjectHoldersIndex<T>>::take(&key);
+        let last = Self::subject_holders_count(subject) - 1;
+        if index != last {
+            let last_holder = Self::subject_holder_by_index((subject, last));
+            <SubjectHoldersArray<T>>::insert((subject, index), last_holder.clone());
+            <SubjectHoldersIndex<T>>::insert((last_holder, subject), index);
+        }
+        <SubjectHoldersArray<T>>::remove((subject, last));
+        <SubjectHoldersCount<T>>::insert(subject, last);
+    }
+
+    fn ensure_subject_owner(subject: Subject, who: &T::AccountId) -> Result {
+        ensure!(<Subjects<T>>::exists(subject), "Subject does not exist.");
+        ensure!(Self::subjects(subject) == *who, "Unauthorized.");
+        Ok(())
+    }
+
+    /// Check that a credential is issued and within its validity window.
+    /// Credentials of a subject retired as historical keep verifying.
+    pub fn check_credential(holder: &T::AccountId, subject: Subject) -> Result {
+        let key = (holder.clone(), subject);
+        ensure!(<Credentials<T>>::exists(&key), "Credential not issued yet.");
+        ensure!(!<Revocations<T>>::exists(&key), "Credential revoked.");
+        ensure!(
+            !Self::retired_subjects(subject).map_or(false, |r| r.invalidated),
+            "Credential invalidated by subject retirement."
+        );
+
+        let cred = Self::credentials(key);
+        ensure!(!cred.suspended, "Credential suspended.");
+
+        let now = <timestamp::Module<T>>::get();
+        ensure!(now >= cred.valid_from, "Credential not yet valid.");
+        if let Some(valid_until) = cred.valid_until {
+            ensure!(now < valid_until, "Credential expired.");
+        }
+
+        Ok(())
+    }
+
+    /// Check that a presented off-chain document matches the
+    /// hash anchored in the credential.
+    pub fn check_document(holder: &T::AccountId, subject: Subject, document: &[u8]) -> Result {
+        Self::check_document_hash(holder, subject, &H256(runtime_io::blake2_256(document)))
+    }
+
+    /// Check that a document hash matches the hash anchored in the credential.
+    pub fn check_document_hash(holder: &T::AccountId, subject: Subject, document_hash: &H256) -> Result {
+        let key = (holder.clone(), subject);
+        ensure!(<Credentials<T>>::exists(&key), "Credential not issued yet.");
+
+        let anchored = Self::credentials(key).document_hash;
+        ensure!(anchored.is_some(), "Credential has no anchored document.");
+        ensure!(anchored.as_ref() == Some(document_hash), "Document does not match anchored hash.");
+
+        Ok(())
+    }
+
+    /// Check a claim disclosed by the holder against the credential's claims root.
+    pub fn check_claim_proof(holder: &T::AccountId, subject: Subject, claim: &SaltedClaim, proof: &ClaimProof) -> Result {
+        let key = (holder.clone(), subject);
+        ensure!(<Credentials<T>>::exists(&key), "Credential not issued yet.");
+
+        let root = Self::credentials(key).claims_root.ok_or("Credential has no claims root.")?;
+        ensure!(merkle::verify_claim(&root, claim, proof), "Invalid claim proof.");
+
+        Ok(())
+    }
+
+    /// Check that the holder holds valid credentials for the prerequisites of a subject.
+    fn check_prerequisites(holder: &T::AccountId, subject: Subject) -> Result {
+        for prerequisite in Self::subject_prerequisites(subject).subjects {
+            ensure!(Self::check_credential(holder, prerequisite).is_ok(), "Prerequisite credential missing.");
+        }
+
+        Ok(())
+    }
+
+    /// Whether `subject` is `of` or among its prerequisites, directly or transitively.
+    fn requires(of: Subject, subject: Subject) -> bool {
+        let (mut pending, mut visited) = (vec![of], Vec::new());
+        while let Some(next) = pending.pop() {
+            if next == subject {
+                return true;
+            }
+            if !visited.contains(&next) {
+                visited.push(next);
+                pending.extend(Self::subject_prerequisites(next).subjects);
+            }
+        }
+
+        false
+    }
+
+    /// Check that claims are within bounds, have unique keys
+    /// and match the schema of the subject.
+    fn check_claims(subject: Subject, claims: &[Claim]) -> Result {
+        ensure!(claims.len() <= MAX_CLAIMS, "Too many claims.");
+        for (i, claim) in claims.iter().enumerate() {
+            ensure!(claim.key.len() <= MAX_CLAIM_KEY_LEN, "Claim key too long.");
+            ensure!(claim.value.len() <= MAX_CLAIM_VALUE_LEN, "Claim value too long.");
+            ensure!(
+                !claims[..i].iter().any(|c| c.key == claim.key),
+                "Duplicate claim key."
+            );
+        }
+
+        let schema = Self::subject_info(subject).schema;
+        for claim in claims {
+            let field = schema.iter().find(|f| f.name == claim.key);
+            ensure!(field.is_some(), "Claim not in subject schema.");
+            ensure!(
+                field.map(|f| f.ty) == Some(claim.value.claim_type()),
+                "Claim type does not match subject schema."
+            );
+        }
+        for field in schema.iter().filter(|f| f.required) {
+            ensure!(
+                claims.iter().any(|c| c.key == field.name),
+                "Required claim missing."
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check that a claim schema is within bounds and has unique field names.
+    fn check_schema(schema: &[SchemaField]) -> Result {
+        ensure!(schema.len() <= MAX_CLAIMS, "Too many schema fields.");
+        for (i, field) in schema.iter().enumerate() {
+            ensure!(field.name.len() <= MAX_CLAIM_KEY_LEN, "Schema field name too long.");
+            ensure!(
+                !schema[..i].iter().any(|f| f.name == field.name),
+                "Duplicate schema field."
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode a value spanning the whole input.
+fn decode_exact<D: Decode>(mut input: &[u8]) -> Option<D> {
+    let value = D::decode(&mut input)?;
+    if input.is_empty() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use primitives::Blake2Hasher;
+  use runtime_io::with_externalities;
+  use runtime_primitives::{
+    testing::{Digest, DigestItem, Header},
+    traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+    BuildStorage,
+  };
+  use support::{assert_noop, assert_ok, impl_outer_origin};
+
+  impl_outer_origin! {
+    pub enum Origin for Test {}
+  }
+
+  // For testing the module, we construct a mock runtime. This means
+  // first constructing a configuration type (`Test`) which implements each of the
+  // configuration traits of modules we use.
+  #[derive(Clone, Eq, PartialEq)]
+  pub struct Test;
+  impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type Digest = Digest;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<u64>;
+    type Header = Header;
+    type Event = ();
+    type Log = DigestItem;
+  }
+  impl timestamp::Trait for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+  }
+  impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+  }
+  impl Trait for Test {
+    type Event = ();
+    type Currency = balances::Module<Test>;
+    type CreateSubjectOrigin = OpenCreation;
+  }
+  impl did::Trait for Test {
+    type Event = ();
+    type Currency = balances::Module<Test>;
+  }
+  type VerifiableCreds = Module<Test>;
+  type Balances = balances::Module<Test>;
+  type DidRegistry = did::Module<Test>;
+
+  // builds the genesis config store and sets mock values
+  fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::<Test>::default()
+      .build_storage()
+      .unwrap()
+      .0;
+    t.extend(
+      balances::GenesisConfig::<Test> {
+        transaction_base_fee: 0,
+        transaction_byte_fee: 0,
+        existential_deposit: 0,
+        transfer_fee: 0,
+        creation_fee: 0,
+        balances: vec![(1, 100), (2, 100), (3, 100), (4, 100), (5, 100)],
+        vesting: vec![],
+      }
+      .build_storage()
+      .unwrap()
+      .0,
+    );
+    t.extend(
+      GenesisConfig::<Test> {
+        subjects: vec![(1, 1), (2, 2)],
+        subject_count: 3,
+        offer_timeout: 100,
+        subject_deposit: 10,
+        credential_deposit: 2,
+        request_deposit: 1,
+        status_list_byte_deposit: 1,
+        max_accreditation_depth: 2,
+        subject_creators: vec![(4, true)],
+      }
+      .build_storage()
+      .unwrap()
+      .0,
+    );
+    t.into()
+  }
+
+  #[test]
+  fn should_fail_issue() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 2, None, None, vec![], None, None),
+            "Unauthorized.");
+    });
+  }
+
+  #[test]
+  fn should_issue() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+    });
+  }
+
+  #[test]
+  fn should_revoke() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 0));
+    });
+  }
+
+  fn licence_schema() -> Vec<SchemaField> {
+    vec![
+        SchemaField { name: b"class".to_vec(), ty: ClaimType::Text, required: true },
+        SchemaField { name: b"born".to_vec(), ty: ClaimType::U64, required: false },
+    ]
+  }
+
+  #[test]
+  fn should_keep_revocation_record() {
+    with_externalities(&mut new_test_ext(), || {
+        <timestamp::Module<Test>>::set_timestamp(10);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 7));
+        assert_eq!(
+            VerifiableCreds::revocations((3, 1)),
+            Some(Revocation { by: 1, when: 10, reason: 7 }));
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1),
+            "Credential revoked.");
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 2),
+            "Credential not issued yet.");
+        assert_noop!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 7),
+            "Credential already revoked.");
+    });
+  }
+
+  #[test]
+  fn should_suspend_and_reinstate() {
+    with_externalities(&mut new_test_ext(), || {
+        <timestamp::Module<Test>>::set_timestamp(10);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        let issued = VerifiableCreds::credentials((3, 1));
+
+        assert_noop!(
+            VerifiableCreds::suspend_credential(Origin::signed(2), 3, 1),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::suspend_credential(Origin::signed(1), 3, 1));
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1),
+            "Credential suspended.");
+
+        <timestamp::Module<Test>>::set_timestamp(20);
+        assert_ok!(
+            VerifiableCreds::reinstate_credential(Origin::signed(1), 3, 1));
+        assert_ok!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1));
+        assert_eq!(
+            VerifiableCreds::credentials((3, 1)), issued);
+        assert_noop!(
+            VerifiableCreds::reinstate_credential(Origin::signed(1), 3, 1),
+            "Credential not suspended.");
+    });
+  }
+
+  #[test]
+  fn should_add_subject() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(3), b"Licence".to_vec(), vec![], licence_schema()));
+        assert_eq!(
+            VerifiableCreds::subjects(3), 3);
+        assert_eq!(
+            VerifiableCreds::subject_info(3).schema, licence_schema());
+    });
+  }
+
+  #[test]
+  fn should_issue_new_subject() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(3), vec![], vec![], vec![]));
+        assert_eq!(
+            VerifiableCreds::subjects(3), 3);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(3), 4, 3, None, None, vec![], None, None));
+    });
+  }
+
+  #[test]
+  fn should_verify_within_validity_window() {
+    with_externalities(&mut new_test_ext(), || {
+        <timestamp::Module<Test>>::set_timestamp(10);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, Some(20), Some(30), vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1),
+            "Credential not yet valid.");
+
+        <timestamp::Module<Test>>::set_timestamp(20);
+        assert_ok!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1));
+
+        <timestamp::Module<Test>>::set_timestamp(30);
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1),
+            "Credential expired.");
+    });
+  }
+
+  #[test]
+  fn should_fail_issue_invalid_window() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, Some(30), Some(20), vec![], None, None),
+            "Invalid validity window.");
+    });
+  }
+
+  #[test]
+  fn should_store_claims() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(1), b"Licence".to_vec(), vec![], licence_schema()));
+        let claims = vec![
+            Claim { key: b"class".to_vec(), value: ClaimValue::Text(b"B".to_vec()) },
+            Claim { key: b"born".to_vec(), value: ClaimValue::U64(19900101) },
+        ];
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 4, 3, None, None, claims.clone(), None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(4), 3));
+        assert_eq!(
+            VerifiableCreds::credentials((4, 3)).claims, claims);
+    });
+  }
+
+  #[test]
+  fn should_fail_issue_duplicate_claims() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(1), b"Licence".to_vec(), vec![], licence_schema()));
+        let claim = Claim { key: b"class".to_vec(), value: ClaimValue::Text(b"B".to_vec()) };
+        assert_noop!(
+            VerifiableCreds::issue_credential(
+                Origin::signed(1), 4, 3, None, None, vec![claim.clone(), claim], None, None),
+            "Duplicate claim key.");
+    });
+  }
+
+  #[test]
+  fn should_fail_issue_claims_not_matching_schema() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(1), b"Licence".to_vec(), vec![], licence_schema()));
+        assert_noop!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 4, 3, None, None, vec![], None, None),
+            "Required claim missing.");
+        assert_noop!(
+            VerifiableCreds::issue_credential(
+                Origin::signed(1), 4, 3, None, None,
+                vec![Claim { key: b"class".to_vec(), value: ClaimValue::U64(2) }], None, None),
+            "Claim type does not match subject schema.");
+        assert_noop!(
+            VerifiableCreds::issue_credential(
+                Origin::signed(1), 4, 3, None, None,
+                vec![Claim { key: b"colour".to_vec(), value: ClaimValue::Bool(true) }], None, None),
+            "Claim not in subject schema.");
+    });
+  }
+
+  #[test]
+  fn should_transfer_subject() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::transfer_subject(Origin::signed(1), 1, 5));
+        assert_noop!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(5), 3, 1, None, None, vec![], None, None));
+        assert_noop!(
+            VerifiableCreds::transfer_subject(Origin::signed(1), 1, 1),
+            "Unauthorized.");
+    });
+  }
+
+  #[test]
+  fn should_manage_subject_issuers() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(
+            VerifiableCreds::add_subject_issuer(Origin::signed(5), 1, 5),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::add_subject_issuer(Origin::signed(1), 1, 5));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(5), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::remove_subject_issuer(Origin::signed(1), 1, 5));
+        assert_noop!(
+            VerifiableCreds::revoke_credential(Origin::signed(5), 3, 1, 0),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 0));
+    });
+  }
+
+  #[test]
+  fn should_require_holder_consent() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert!(VerifiableCreds::credential_offers((3, 1)).is_some());
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1),
+            "Credential not issued yet.");
+        assert_noop!(
+            VerifiableCreds::accept_credential(Origin::signed(4), 1),
+            "No credential offer.");
+
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_eq!(VerifiableCreds::credential_offers((3, 1)), None);
+        assert_ok!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1));
+    });
+  }
+
+  #[test]
+  fn should_reject_offer() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::reject_credential(Origin::signed(3), 1));
+        assert_noop!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1),
+            "No credential offer.");
+        assert!(!<Credentials<Test>>::exists((3, 1)));
+    });
+  }
+
+  #[test]
+  fn should_fail_accept_expired_offer() {
+    with_externalities(&mut new_test_ext(), || {
+        <timestamp::Module<Test>>::set_timestamp(10);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+
+        <timestamp::Module<Test>>::set_timestamp(110);
+        assert_noop!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1),
+            "Credential offer expired.");
+        assert_ok!(
+            VerifiableCreds::reject_credential(Origin::signed(3), 1));
+    });
+  }
+
+  #[test]
+  fn should_approve_request() {
+    with_externalities(&mut new_test_ext(), || {
+        let evidence = H256::repeat_byte(1);
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(3), 1, evidence));
+        assert_eq!(
+            VerifiableCreds::issuer_requests(1), vec![(3, 1)]);
+        assert_eq!(Balances::reserved_balance(&3), 1);
+        assert_noop!(
+            VerifiableCreds::request_credential(Origin::signed(3), 1, evidence),
+            "Credential already requested.");
+        assert_noop!(
+            VerifiableCreds::approve_request(Origin::signed(2), 3, 1, None, None, vec![], None, None),
+            "Unauthorized.");
+
+        assert_ok!(
+            VerifiableCreds::approve_request(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_eq!(VerifiableCreds::credential_requests((3, 1)), None);
+        assert!(VerifiableCreds::issuer_requests(1).is_empty());
+        assert_eq!(Balances::reserved_balance(&3), 0);
+        assert_ok!(
+            VerifiableCreds::verify_credential(Origin::signed(4), 3, 1));
+    });
+  }
+
+  #[test]
+  fn should_deny_request() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(3), 1, H256::zero()));
+        assert_ok!(
+            VerifiableCreds::deny_request(Origin::signed(1), 3, 1, 2));
+        assert!(VerifiableCreds::issuer_requests(1).is_empty());
+        assert_noop!(
+            VerifiableCreds::approve_request(Origin::signed(1), 3, 1, None, None, vec![], None, None),
+            "No credential request.");
+    });
+  }
+
+  #[test]
+  fn should_release_request_deposits() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(3), 1, H256::zero()));
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(4), 1, H256::zero()));
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(4), 2, H256::zero()));
+        assert_ok!(
+            VerifiableCreds::cancel_request(Origin::signed(3), 1));
+        assert_eq!(Balances::reserved_balance(&3), 0);
+        assert_noop!(
+            VerifiableCreds::cancel_request(Origin::signed(3), 1),
+            "No credential request.");
+
+        // Retiring a subject drops its open requests.
+        assert_ok!(
+            VerifiableCreds::retire_subject(Origin::signed(1), 1, false));
+        assert_eq!(VerifiableCreds::credential_requests((4, 1)), None);
+        assert!(VerifiableCreds::issuer_requests(1).is_empty());
+        assert_eq!(Balances::reserved_balance(&4), 1);
+
+        <balances::FreeBalance<Test>>::insert(5, 0);
+        assert_noop!(
+            VerifiableCreds::request_credential(Origin::signed(5), 2, H256::zero()),
+            "Insufficient balance for request deposit.");
+    });
+  }
+
+  #[test]
+  fn should_move_requests_with_subject() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(3), 1, H256::zero()));
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(3), 2, H256::zero()));
+        assert_ok!(
+            VerifiableCreds::transfer_subject(Origin::signed(1), 1, 2));
+        assert!(VerifiableCreds::issuer_requests(1).is_empty());
+        assert_eq!(
+            VerifiableCreds::issuer_requests(2), vec![(3, 2), (3, 1)]);
+    });
+  }
+
+  #[test]
+  fn should_bound_requests_moved_with_subject() {
+    with_externalities(&mut new_test_ext(), || {
+        for holder in 10..10 + MAX_OPEN_REQUESTS as u64 {
+            <balances::FreeBalance<Test>>::insert(holder, 1);
+            assert_ok!(
+                VerifiableCreds::request_credential(Origin::signed(holder), 2, H256::zero()));
+        }
+        assert_ok!(
+            VerifiableCreds::request_credential(Origin::signed(3), 1, H256::zero()));
+        assert_noop!(
+            VerifiableCreds::transfer_subject(Origin::signed(1), 1, 2),
+            "Too many open requests.");
+    });
+  }
+
+  #[test]
+  fn should_index_credentials() {
+    with_externalities(&mut new_test_ext(), || {
+        for (holder, subject) in vec![(3, 1), (4, 1), (3, 2), (5, 1)] {
+            assert_ok!(
+                VerifiableCreds::issue_credential(Origin::signed(subject as u64), holder, subject, None, None, vec![], None, None));
+            assert_ok!(
+                VerifiableCreds::accept_credential(Origin::signed(holder), subject));
+        }
+        assert_eq!(VerifiableCreds::holder_subjects(&3, 0, 10), vec![1, 2]);
+        assert_eq!(VerifiableCreds::subject_holders(1, 0, 10), vec![3, 4, 5]);
+        assert_eq!(VerifiableCreds::subject_holders(1, 1, 1), vec![4]);
+        assert_eq!(VerifiableCreds::subject_holders(1, 5, 10), Vec::<u64>::new());
+
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 0));
+        assert_eq!(VerifiableCreds::holder_subjects(&3, 0, 10), vec![2]);
+        assert_eq!(VerifiableCreds::subject_holders(1, 0, 10), vec![5, 4]);
+
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_eq!(VerifiableCreds::holder_subjects(&3, 0, 10), vec![2, 1]);
+        assert_eq!(VerifiableCreds::subject_holders(1, 0, 10), vec![5, 4, 3]);
+    });
+  }
+
+  #[test]
+  fn should_issue_and_revoke_batch() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credentials_batch(
+                Origin::signed(1), 1, vec![(3, vec![]), (4, vec![]), (5, vec![])]));
+        for holder in 3..6 {
+            assert_ok!(
+                VerifiableCreds::accept_credential(Origin::signed(holder), 1));
+        }
+        assert_eq!(VerifiableCreds::subject_holders(1, 0, 10), vec![3, 4, 5]);
+
+        assert_ok!(
+            VerifiableCreds::revoke_credentials_batch(Origin::signed(1), 1, vec![3, 5], 1));
+        assert_eq!(VerifiableCreds::subject_holders(1, 0, 10), vec![4]);
+        assert!(VerifiableCreds::revocations((5, 1)).is_some());
+    });
+  }
+
+  #[test]
+  fn should_fail_batch_atomically() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(
+            VerifiableCreds::issue_credentials_batch(
+                Origin::signed(1), 1, vec![(3, vec![]), (4, vec![]), (3, vec![])]),
+            "Duplicate holder in batch.");
+
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_noop!(
+            VerifiableCreds::revoke_credentials_batch(Origin::signed(1), 1, vec![3, 4], 1),
+            "Credential not issued yet.");
+    });
+  }
+
+  #[test]
+  fn should_reserve_subject_deposit() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(3), vec![], vec![], vec![]));
+        assert_eq!(Balances::reserved_balance(&3), 10);
+        assert_eq!(VerifiableCreds::subject_deposits(3), Some((3, 10)));
+
+        <balances::FreeBalance<Test>>::insert(4, 5);
+        assert_noop!(
+            VerifiableCreds::create_subject(Origin::signed(4), vec![], vec![], vec![]),
+            "Insufficient balance for subject deposit.");
+    });
+  }
+
+  #[test]
+  fn should_reserve_credential_deposit() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_eq!(Balances::reserved_balance(&1), 2);
+        // Replacing an offer doesn't reserve twice.
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_eq!(Balances::reserved_balance(&1), 2);
+
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_eq!(VerifiableCreds::credential_deposits((3, 1)), Some((1, 2)));
+        assert_eq!(Balances::reserved_balance(&1), 2);
+
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 0));
+        assert_eq!(Balances::reserved_balance(&1), 0);
+        assert_eq!(VerifiableCreds::credential_deposits((3, 1)), None);
+    });
+  }
+
+  #[test]
+  fn should_release_deposit_of_rejected_offer() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credentials_batch(Origin::signed(1), 1, vec![(3, vec![]), (4, vec![])]));
+        assert_eq!(Balances::reserved_balance(&1), 4);
+        assert_ok!(
+            VerifiableCreds::reject_credential(Origin::signed(3), 1));
+        assert_eq!(Balances::reserved_balance(&1), 2);
     });
   }
 
   #[test]
-  fn should_issue_new_subject() {
+  fn should_fail_accept_offer_of_removed_issuer() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::add_subject_issuer(Origin::signed(1), 1, 2));
+        assert_ok!(
+            VerifiableCreds::issue_credentials_batch(Origin::signed(2), 1, vec![(3, vec![]), (4, vec![])]));
+        assert_ok!(
+            VerifiableCreds::remove_subject_issuer(Origin::signed(1), 1, 2));
+        assert_noop!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1),
+            "Unauthorized.");
+
+        // The subject owner can withdraw the removed issuer's offers.
+        assert_ok!(
+            VerifiableCreds::withdraw_offer(Origin::signed(1), 4, 1));
+        assert_eq!(Balances::reserved_balance(&2), 2);
+    });
+  }
+
+  #[test]
+  fn should_withdraw_and_clear_offers() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credentials_batch(Origin::signed(1), 1, vec![(3, vec![]), (4, vec![])]));
+        assert_noop!(
+            VerifiableCreds::withdraw_offer(Origin::signed(2), 3, 1),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::withdraw_offer(Origin::signed(1), 3, 1));
+        assert_eq!(VerifiableCreds::credential_offers((3, 1)), None);
+        assert_eq!(Balances::reserved_balance(&1), 2);
+
+        assert_noop!(
+            VerifiableCreds::clear_expired_offer(Origin::signed(5), 4, 1),
+            "Credential offer not expired.");
+        <timestamp::Module<Test>>::set_timestamp(100);
+        assert_ok!(
+            VerifiableCreds::clear_expired_offer(Origin::signed(5), 4, 1));
+        assert_eq!(VerifiableCreds::offer_deposits((4, 1)), None);
+        assert_eq!(Balances::reserved_balance(&1), 0);
+        assert_noop!(
+            VerifiableCreds::clear_expired_offer(Origin::signed(5), 4, 1),
+            "No credential offer.");
+    });
+  }
+
+  #[test]
+  fn should_retire_subject_keeping_history() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::create_subject(Origin::signed(3), vec![], vec![], vec![]));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(3), 4, 3, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(4), 3));
+
+        assert_noop!(
+            VerifiableCreds::retire_subject(Origin::signed(4), 3, false),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::retire_subject(Origin::signed(3), 3, false));
+        // Only the credential deposit is left reserved.
+        assert_eq!(Balances::reserved_balance(&3), 2);
+        assert_noop!(
+            VerifiableCreds::issue_credential(Origin::signed(3), 5, 3, None, None, vec![], None, None),
+            "Subject retired.");
+        assert_ok!(
+            VerifiableCreds::verify_credential(Origin::signed(5), 4, 3));
+    });
+  }
+
+  #[test]
+  fn should_retire_subject_invalidating_credentials() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::retire_subject(Origin::signed(1), 1, true));
+        assert_noop!(
+            VerifiableCreds::verify_credential(Origin::signed(5), 3, 1),
+            "Credential invalidated by subject retirement.");
+        assert_eq!(Balances::reserved_balance(&1), 0);
+        assert_eq!(VerifiableCreds::credential_deposits((3, 1)), None);
+        assert_eq!(VerifiableCreds::subject_holders_count(1), 0);
+        assert_eq!(VerifiableCreds::holder_subjects_count(3), 0);
+        assert_noop!(
+            VerifiableCreds::release_retired_credentials(Origin::signed(5), 1),
+            "No credentials left to release.");
+        assert_noop!(
+            VerifiableCreds::release_retired_credentials(Origin::signed(5), 2),
+            "Subject not retired with invalidation.");
+        assert_noop!(
+            VerifiableCreds::retire_subject(Origin::signed(1), 1, false),
+            "Subject retired.");
+    });
+  }
+
+  #[test]
+  fn should_verify_anchored_document() {
+    with_externalities(&mut new_test_ext(), || {
+        let document = br#"{"@context": ["https://www.w3.org/2018/credentials/v1"]}"#;
+        let hash = H256(runtime_io::blake2_256(document));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], Some(hash), None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+
+        assert_ok!(VerifiableCreds::check_document(&3, 1, document));
+        assert_eq!(
+            VerifiableCreds::check_document(&3, 1, b"{}"),
+            Err("Document does not match anchored hash."));
+        assert_ok!(
+            VerifiableCreds::verify_document_hash(Origin::signed(4), 3, 1, hash));
+    });
+  }
+
+  #[test]
+  fn should_fail_verify_without_anchored_document() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_noop!(
+            VerifiableCreds::verify_document_hash(Origin::signed(4), 3, 1, H256::zero()),
+            "Credential has no anchored document.");
+    });
+  }
+
+  #[test]
+  fn should_reference_issuer_and_holder_dids() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            DidRegistry::create_did(Origin::signed(1), vec![], vec![]));
+        assert_ok!(
+            DidRegistry::create_did(Origin::signed(3), vec![], vec![]));
+        let (issuer_did, holder_did) = (DidRegistry::did_of(1), DidRegistry::did_of(3));
+
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        let cred = VerifiableCreds::credentials((3, 1));
+        assert_eq!(cred.issuer_did, issuer_did);
+        assert_eq!(cred.holder_did, holder_did);
+
+        // Rotating the controller keeps the DIDs the credential refers to.
+        assert_ok!(
+            DidRegistry::set_did_controller(Origin::signed(3), holder_did.unwrap(), 4));
+        assert_ok!(VerifiableCreds::check_credential(&3, 1));
+        assert_eq!(VerifiableCreds::credentials((3, 1)).holder_did, holder_did);
+    });
+  }
+
+  #[test]
+  fn should_claim_did_credentials_after_key_rotation() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            DidRegistry::create_did(Origin::signed(3), vec![], vec![]));
+        let holder_did = DidRegistry::did_of(3);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(2), 3, 2, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 2));
+        assert_eq!(Balances::reserved_balance(&1), 2);
+
+        assert_noop!(
+            VerifiableCreds::claim_did_credentials(Origin::signed(4), 3, vec![1]),
+            "Account controls no DID.");
+        assert_ok!(
+            DidRegistry::set_did_controller(Origin::signed(3), holder_did.unwrap(), 4));
+        assert_noop!(
+            VerifiableCreds::claim_did_credentials(Origin::signed(4), 3, vec![1, 1]),
+            "Duplicate subject in batch.");
+        assert_noop!(
+            VerifiableCreds::claim_did_credentials(Origin::signed(4), 3, vec![1, 3]),
+            "Credential not issued yet.");
+
+        assert_ok!(
+            VerifiableCreds::claim_did_credentials(Origin::signed(4), 3, vec![1, 2]));
+        assert_noop!(VerifiableCreds::check_credential(&3, 1), "Credential not issued yet.");
+        assert_ok!(VerifiableCreds::check_credential(&4, 1));
+        assert_ok!(VerifiableCreds::check_credential(&4, 2));
+        assert_eq!(VerifiableCreds::credentials((4, 1)).holder_did, holder_did);
+        assert_eq!(VerifiableCreds::holder_subjects_count(3), 0);
+        assert_eq!(VerifiableCreds::holder_subjects_count(4), 2);
+        // The deposit stays reserved from the issuer, and moves with the credential.
+        assert_eq!(VerifiableCreds::credential_deposits((4, 1)), Some((1, 2)));
+        assert_eq!(Balances::reserved_balance(&1), 2);
+    });
+  }
+
+  #[test]
+  fn should_fail_claim_credentials_not_held_through_did() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            DidRegistry::create_did(Origin::signed(4), vec![], vec![]));
+
+        assert_noop!(
+            VerifiableCreds::claim_did_credentials(Origin::signed(4), 3, vec![1]),
+            "Credential not held through the DID.");
+    });
+  }
+
+  fn salted_claims() -> Vec<SaltedClaim> {
+    vec![
+      (b"name".to_vec(), ClaimValue::Text(b"Alice".to_vec())),
+      (b"born".to_vec(), ClaimValue::U64(1990)),
+      (b"over_18".to_vec(), ClaimValue::Bool(true)),
+    ].into_iter().enumerate().map(|(i, (key, value))| SaltedClaim {
+      claim: Claim { key, value },
+      salt: H256::repeat_byte(i as u8 + 1),
+    }).collect()
+  }
+
+  #[test]
+  fn should_disclose_single_claims() {
+    with_externalities(&mut new_test_ext(), || {
+        let claims = salted_claims();
+        let root = merkle::claims_root(&claims);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, root));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+
+        for (index, claim) in claims.iter().enumerate() {
+            let proof = merkle::prove_claim(&claims, index).unwrap();
+            assert_ok!(VerifiableCreds::check_claim_proof(&3, 1, claim, &proof));
+        }
+
+        // A proof does not carry over to another or an altered claim.
+        let proof = merkle::prove_claim(&claims, 2).unwrap();
+        assert_eq!(
+            VerifiableCreds::check_claim_proof(&3, 1, &claims[1], &proof),
+            Err("Invalid claim proof."));
+        let mut altered = claims[2].clone();
+        altered.claim.value = ClaimValue::Bool(false);
+        assert_eq!(
+            VerifiableCreds::check_claim_proof(&3, 1, &altered, &proof),
+            Err("Invalid claim proof."));
+    });
+  }
+
+  #[test]
+  fn should_fail_claim_proof_without_claims_root() {
+    with_externalities(&mut new_test_ext(), || {
+        let claims = salted_claims();
+        let proof = merkle::prove_claim(&claims, 0).unwrap();
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_eq!(
+            VerifiableCreds::check_claim_proof(&3, 1, &claims[0], &proof),
+            Err("Credential has no claims root."));
+    });
+  }
+
+  #[test]
+  fn should_set_status_bits_in_bulk() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(
+            VerifiableCreds::create_status_list(Origin::signed(2), 1, 16),
+            "Unauthorized.");
+        assert_ok!(
+            VerifiableCreds::create_status_list(Origin::signed(1), 1, 16));
+        assert_eq!(VerifiableCreds::status_lists(0).unwrap().bits, vec![0, 0]);
+        assert_eq!(VerifiableCreds::status_list_deposits(0), Some((1, 2)));
+        assert_eq!(Balances::reserved_balance(&1), 2);
+        assert_noop!(
+            VerifiableCreds::create_status_list(Origin::signed(1), 1, 1024),
+            "Insufficient balance for status list deposit.");
+
+        assert_ok!(
+            VerifiableCreds::set_status_bits(Origin::signed(1), 0, vec![0, 9, 15], true));
+        assert_eq!(VerifiableCreds::status_lists(0).unwrap().bits, vec![0b1000_0000, 0b0100_0001]);
+        assert_ok!(
+            VerifiableCreds::set_status_bits(Origin::signed(1), 0, vec![9], false));
+        assert_eq!(VerifiableCreds::status_revoked(0, 0), Some(true));
+        assert_eq!(VerifiableCreds::status_revoked(0, 9), Some(false));
+        assert_eq!(VerifiableCreds::status_revoked(0, 16), None);
+
+        assert_noop!(
+            VerifiableCreds::set_status_bits(Origin::signed(1), 0, vec![3, 16], true),
+            "Status index out of range.");
+        assert_noop!(
+            VerifiableCreds::set_status_bits(Origin::signed(2), 0, vec![3], true),
+            "Unauthorized.");
+    });
+  }
+
+  #[test]
+  fn should_remove_status_list() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::add_subject_issuer(Origin::signed(1), 1, 2));
+        assert_ok!(
+            VerifiableCreds::create_status_list(Origin::signed(2), 1, 16));
+        assert_ok!(
+            VerifiableCreds::create_status_list(Origin::signed(2), 1, 8));
+        assert_eq!(Balances::reserved_balance(&2), 3);
+
+        assert_noop!(
+            VerifiableCreds::remove_status_list(Origin::signed(3), 0),
+            "Unauthorized.");
+        // The list owner and the subject owner can both remove a list.
+        assert_ok!(
+            VerifiableCreds::remove_status_list(Origin::signed(2), 0));
+        assert_ok!(
+            VerifiableCreds::remove_status_list(Origin::signed(1), 1));
+        assert_eq!(VerifiableCreds::status_lists(0), None);
+        assert_eq!(VerifiableCreds::status_list_deposits(1), None);
+        assert_eq!(Balances::reserved_balance(&2), 0);
+        assert_eq!(VerifiableCreds::status_revoked(0, 0), None);
+        assert_noop!(
+            VerifiableCreds::remove_status_list(Origin::signed(2), 0),
+            "Status list does not exist.");
+    });
+  }
+
+  #[test]
+  fn should_return_accreditation_chain() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(
+            VerifiableCreds::accredit_issuer(Origin::signed(1), 1, 7),
+            "bad origin: expected to be a root origin");
+        assert_ok!(
+            VerifiableCreds::accredit_issuer(Origin::ROOT, 1, 7));
+        assert_ok!(
+            VerifiableCreds::accredit_sub_issuer(Origin::signed(1), 5, 7));
+        assert_noop!(
+            VerifiableCreds::accredit_sub_issuer(Origin::signed(5), 4, 7),
+            "Accreditation depth limit reached.");
+        // Re-accrediting would silently move a sub-issuer's place in the chain.
+        assert_noop!(
+            VerifiableCreds::accredit_issuer(Origin::ROOT, 5, 7),
+            "Issuer already accredited.");
+        assert_noop!(
+            VerifiableCreds::accredit_issuer(Origin::ROOT, 1, 7),
+            "Issuer already accredited.");
+
+        assert_ok!(
+            VerifiableCreds::set_subject_category(Origin::signed(1), 1, 7));
+        assert_ok!(
+            VerifiableCreds::add_subject_issuer(Origin::signed(1), 1, 5));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(5), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_eq!(VerifiableCreds::credential_accreditation(&3, 1), Some(vec![1, 5]));
+
+        // Withdrawing an accreditation breaks the chains below it.
+        assert_ok!(
+            VerifiableCreds::remove_accreditation(Origin::ROOT, 1, 7));
+        assert_eq!(VerifiableCreds::credential_accreditation(&3, 1), None);
+        assert_noop!(
+            VerifiableCreds::accredit_sub_issuer(Origin::signed(5), 4, 7),
+            "Unauthorized.");
+    });
+  }
+
+  #[test]
+  fn should_manage_subject_creator_allowlist() {
     with_externalities(&mut new_test_ext(), || {
+        assert_eq!(
+            <AllowlistCreation as EnsureSubjectCreator<Test>>::ensure_creator(Origin::signed(4)), Ok(4));
+        assert_eq!(
+            <AllowlistCreation as EnsureSubjectCreator<Test>>::ensure_creator(Origin::signed(3)),
+            Err("Not allowed to create subjects."));
+
+        assert_noop!(
+            VerifiableCreds::add_subject_creator(Origin::signed(4), 3),
+            "bad origin: expected to be a root origin");
+        assert_ok!(
+            VerifiableCreds::add_subject_creator(Origin::ROOT, 3));
+        assert_eq!(
+            <AllowlistCreation as EnsureSubjectCreator<Test>>::ensure_creator(Origin::signed(3)), Ok(3));
+
         assert_ok!(
-            VerifiableCreds::create_subject(Origin::signed(3)));
+            VerifiableCreds::remove_subject_creator(Origin::ROOT, 4));
+        assert_noop!(
+            VerifiableCreds::remove_subject_creator(Origin::ROOT, 4),
+            "Not a subject creator.");
         assert_eq!(
-            VerifiableCreds::issuers(3), 3);
+            <AllowlistCreation as EnsureSubjectCreator<Test>>::ensure_creator(Origin::signed(4)),
+            Err("Not allowed to create subjects."));
+    });
+  }
+
+  #[test]
+  fn should_require_prerequisites() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::set_subject_prerequisites(Origin::signed(2), 2, vec![1], PrerequisiteRevocation::Cascade));
+        assert_noop!(
+            VerifiableCreds::set_subject_prerequisites(Origin::signed(1), 1, vec![2], PrerequisiteRevocation::Cascade),
+            "Prerequisite cycle.");
+        assert_noop!(
+            VerifiableCreds::issue_credential(Origin::signed(2), 3, 2, None, None, vec![], None, None),
+            "Prerequisite credential missing.");
+
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(2), 3, 2, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 2));
+
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 0));
+        assert_eq!(VerifiableCreds::check_credential(&3, 2), Err("Credential revoked."));
+    });
+  }
+
+  #[test]
+  fn should_flag_dependent_credentials_for_review() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(
+            VerifiableCreds::set_subject_prerequisites(Origin::signed(2), 2, vec![1], PrerequisiteRevocation::FlagForReview));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(2), 3, 2, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 2));
+
+        assert_ok!(
+            VerifiableCreds::revoke_credential(Origin::signed(1), 3, 1, 0));
+        assert_eq!(VerifiableCreds::check_credential(&3, 2), Err("Credential suspended."));
+        assert_ok!(
+            VerifiableCreds::reinstate_credential(Origin::signed(2), 3, 2));
+        assert_ok!(VerifiableCreds::check_credential(&3, 2));
+    });
+  }
+
+  #[test]
+  fn should_transfer_credential() {
+    with_externalities(&mut new_test_ext(), || {
+        <timestamp::Module<Test>>::set_timestamp(10);
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 3, 1, None, None, vec![], None, None));
+        assert_ok!(
+            VerifiableCreds::accept_credential(Origin::signed(3), 1));
+        assert_noop!(
+            VerifiableCreds::transfer_credential(Origin::signed(3), 1, 4),
+            "Credential not transferable.");
+
+        assert_ok!(
+            VerifiableCreds::set_subject_transferable(Origin::signed(1), 1, true));
+        <timestamp::Module<Test>>::set_timestamp(20);
+        assert_ok!(
+            VerifiableCreds::transfer_credential(Origin::signed(3), 1, 5));
+        assert_ok!(
+            VerifiableCreds::cancel_transfer(Origin::signed(5), 3, 1));
+        assert_noop!(
+            VerifiableCreds::accept_transfer(Origin::signed(5), 3, 1),
+            "No credential transfer.");
+
+        // Nothing moves until the recipient accepts.
+        assert_ok!(
+            VerifiableCreds::transfer_credential(Origin::signed(3), 1, 4));
+        assert_eq!(VerifiableCreds::pending_transfer((3, 1)), Some(4));
+        assert!(!<Credentials<Test>>::exists((4, 1)));
+        assert_noop!(
+            VerifiableCreds::accept_transfer(Origin::signed(5), 3, 1),
+            "No credential transfer.");
+        assert_ok!(
+            VerifiableCreds::accept_transfer(Origin::signed(4), 3, 1));
+
+        assert_eq!(VerifiableCreds::pending_transfer((3, 1)), None);
+        let cred = VerifiableCreds::credentials((4, 1));
+        assert_eq!((cred.by, cred.when), (1, 10));
+        assert!(!<Credentials<Test>>::exists((3, 1)));
+        assert_ok!(VerifiableCreds::check_credential(&4, 1));
+        assert_eq!(VerifiableCreds::holder_subjects(&3, 0, 10), Vec::<u32>::new());
+        assert_eq!(VerifiableCreds::subject_holders(1, 0, 10), vec![4]);
+        assert_eq!(VerifiableCreds::credential_deposits((4, 1)), Some((1, 2)));
+        assert_noop!(
+            VerifiableCreds::transfer_credential(Origin::signed(3), 1, 5),
+            "Credential not issued yet.");
+    });
+  }
+
+  #[test]
+  fn should_migrate_legacy_credentials() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(VerifiableCreds::storage_version(), STORAGE_VERSION);
+        assert_noop!(
+            VerifiableCreds::queue_credential_migrations(Origin::ROOT, vec![], true),
+            "Storage already migrated.");
+
+        // Build the state of a chain started before the storage was versioned,
+        // holding a legacy credential next to one already in the current layout.
+        assert_ok!(
+            VerifiableCreds::issue_credential(Origin::signed(1), 4, 1, None, None, vec![], None, None));
         assert_ok!(
-            VerifiableCreds::issue_credential(Origin::signed(3), 4, 3));
+            VerifiableCreds::accept_credential(Origin::signed(4), 1));
+        let current = VerifiableCreds::credentials((4, 1));
+        <StorageVersion<Test>>::kill();
+        let legacy = CredentialV0 { subject: 2, when: 7, by: 2 };
+        support::storage::put(&<Credentials<Test>>::key_for(&(3, 2)), &legacy);
+
+        // A credential awaiting migration is migrated before it is changed.
+        assert_ok!(
+            VerifiableCreds::suspend_credential(Origin::signed(2), 3, 2));
+        let cred = VerifiableCreds::credentials((3, 2));
+        assert_eq!((cred.subject, cred.when, cred.by, cred.valid_from, cred.suspended), (2, 7, 2, 7, true));
+        assert_eq!(VerifiableCreds::holder_subjects(&3, 0, 10), vec![2]);
+        assert_ok!(
+            VerifiableCreds::reinstate_credential(Origin::signed(2), 3, 2));
+
+        assert_ok!(
+            VerifiableCreds::queue_credential_migrations(Origin::ROOT, vec![(3, 2), (4, 1)], false));
+        assert_eq!(VerifiableCreds::pending_migration_count(), 2);
+        VerifiableCreds::on_initialize(1);
+        assert_eq!(VerifiableCreds::storage_version(), 0);
+        assert_eq!(VerifiableCreds::pending_migration_count(), 0);
+        assert_ok!(
+            VerifiableCreds::queue_credential_migrations(Origin::ROOT, vec![(5, 2)], true));
+        VerifiableCreds::on_initialize(2);
+        assert_eq!(VerifiableCreds::storage_version(), STORAGE_VERSION);
+        assert_eq!(VerifiableCreds::pending_migration_count(), 0);
+        assert!(!<PendingMigrations<Test>>::exists(0));
+
+        // Migrating an already migrated credential leaves it untouched.
+        let cred = VerifiableCreds::credentials((3, 2));
+        assert_eq!((cred.subject, cred.when, cred.by, cred.suspended), (2, 7, 2, false));
+        assert_ok!(VerifiableCreds::check_credential(&3, 2));
+        assert_eq!(VerifiableCreds::holder_subjects(&3, 0, 10), vec![2]);
+        assert_eq!(VerifiableCreds::credentials((4, 1)), current);
+        assert!(!<Credentials<Test>>::exists((5, 2)));
     });
   }
-}
\ No newline at end of file
+}
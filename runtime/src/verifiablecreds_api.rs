@@ -0,0 +1,51 @@
+//! Runtime API for read-only queries of verifiable credentials.
+//! Verifiers can call it on any node without submitting a transaction.
+
+use client::decl_runtime_apis;
+use rstd::prelude::*;
+use crate::{AccountId, Moment};
+use crate::merkle::{ClaimProof, SaltedClaim};
+use crate::verifiablecreds::{Credential, Prerequisites, Retirement, Revocation, StatusList, StatusListId, Subject, SubjectInfo};
+
+decl_runtime_apis! {
+    /// The API to query credentials and subjects.
+    pub trait VerifiableCredsApi {
+        /// Whether the holder's credential for the subject currently verifies.
+        fn is_valid(holder: AccountId, subject: Subject) -> bool;
+        /// The credential the holder has been issued for the subject, if any.
+        fn credential(holder: AccountId, subject: Subject) -> Option<Credential<Moment, AccountId>>;
+        /// The owner of the subject, if the subject exists.
+        fn subject_issuer(subject: Subject) -> Option<AccountId>;
+        /// Verify the holder's credential for the subject,
+        /// returning the reason it does not verify on failure.
+        fn verify(holder: AccountId, subject: Subject) -> Result<(), Vec<u8>>;
+        /// Verify the holder's credential for the subject along with the
+        /// off-chain document presented for it, which must match the anchored hash.
+        fn verify_document(holder: AccountId, subject: Subject, document: Vec<u8>) -> Result<(), Vec<u8>>;
+        /// Verify the holder's credential for the subject along with a single
+        /// claim disclosed from it, which must be included in the claims root.
+        fn verify_claim(holder: AccountId, subject: Subject, claim: SaltedClaim, proof: ClaimProof) -> Result<(), Vec<u8>>;
+        /// The revocation record of the holder's credential for the subject, if revoked.
+        fn revocation(holder: AccountId, subject: Subject) -> Option<Revocation<Moment, AccountId>>;
+        /// The metadata of the subject.
+        fn subject_info(subject: Subject) -> SubjectInfo;
+        /// The retirement record of the subject, if retired.
+        fn subject_retirement(subject: Subject) -> Option<Retirement<Moment>>;
+        /// The prerequisites of the subject.
+        fn subject_prerequisites(subject: Subject) -> Prerequisites;
+        /// Whether holders may transfer their credentials for the subject.
+        fn is_transferable(subject: Subject) -> bool;
+        /// The additional issuers authorized for the subject.
+        fn subject_issuers(subject: Subject) -> Vec<AccountId>;
+        /// A page of the subjects the holder holds unrevoked credentials for.
+        fn holder_credentials(holder: AccountId, start: u32, limit: u32) -> Vec<Subject>;
+        /// A page of the holders of unrevoked credentials for the subject.
+        fn subject_holders(subject: Subject, start: u32, limit: u32) -> Vec<AccountId>;
+        /// The chain of accredited issuers from the root authority to the issuer of
+        /// the holder's credential for the subject, if that issuer is accredited
+        /// for the subject's category.
+        fn accreditation_chain(holder: AccountId, subject: Subject) -> Option<Vec<AccountId>>;
+        /// The status list, if it exists.
+        fn status_list(list: StatusListId) -> Option<StatusList<Moment, AccountId>>;
+    }
+}
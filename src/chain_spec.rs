@@ -1,7 +1,7 @@
 use primitives::{ed25519, sr25519, Pair};
 use substrate_verifiable_credentials_runtime::{
 	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
-	SudoConfig, IndicesConfig, VerifiableCredsConfig
+	SudoConfig, IndicesConfig, DidRegistryConfig, VerifiableCredsConfig
 };
 use substrate_service;
 
@@ -115,9 +115,19 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		did: Some(DidRegistryConfig {
+			did_deposit: 1_000,
+		}),
 		verifiablecreds: Some(VerifiableCredsConfig {
-			issuers: vec![(account_key("Alice"), 1), (account_key("Bob"), 2)],
-			subject_nonce: 3,
+			subjects: vec![(1, account_key("Alice")), (2, account_key("Bob"))],
+			subject_count: 3,
+			offer_timeout: 7 * 24 * 60 * 60, // one week.
+			subject_deposit: 10_000,
+			credential_deposit: 100,
+			request_deposit: 10,
+			status_list_byte_deposit: 10,
+			max_accreditation_depth: 3,
+			subject_creators: vec![],
 		}),
 	}
 }
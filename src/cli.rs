@@ -1,13 +1,19 @@
-use crate::service;
-use futures::{future, Future, sync::oneshot};
+use crate::{presentation, rpc, service};
+use futures::{future, Future, Stream, sync::oneshot};
 use std::cell::RefCell;
 use tokio::runtime::Runtime;
 pub use substrate_cli::{VersionInfo, IntoExit, error};
-use substrate_cli::{informant, parse_and_execute, NoCustom};
+use substrate_cli::{informant, parse_and_execute, impl_augment_clap, GetLogFilter};
+use structopt::StructOpt;
+use serde_json::{json, Value};
 use substrate_service::{ServiceFactory, Roles as ServiceRoles};
 use crate::chain_spec;
 use std::ops::Deref;
-use log::info;
+use log::{info, warn};
+use std::net::SocketAddr;
+use parity_codec::Encode;
+use primitives::{bytes::from_hex, hexdisplay::HexDisplay, sr25519, Pair};
+use substrate_verifiable_credentials_runtime::Hash;
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()> where
@@ -15,9 +21,9 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
-	parse_and_execute::<service::Factory, NoCustom, NoCustom, _, _, _, _, _>(
+	let custom = parse_and_execute::<service::Factory, CustomCommand, RunParams, _, _, _, _, _>(
 		load_spec, &version, "substrate-node", args, exit,
-	 	|exit, _custom_args, config| {
+	 	|exit, run_params, config| {
 			info!("{}", version.name);
 			info!("  version {}", config.full_version());
 			info!("  by {}, 2017, 2018", version.author);
@@ -32,14 +38,165 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 				 	service::Factory::new_light(config, executor).map_err(|e| format!("{:?}", e))?,
 					exit
 				),
-				_ => run_until_exit(
-					runtime,
-					service::Factory::new_full(config, executor).map_err(|e| format!("{:?}", e))?,
-					exit
-				),
+				_ => {
+					let service = service::Factory::new_full(config, executor).map_err(|e| format!("{:?}", e))?;
+					// The node keeps running without the credential RPC,
+					// e.g. when another local node already serves it.
+					let _rpc = run_params.vc_rpc_address().and_then(|address| {
+						rpc::start_http(&address, service.client())
+							.map_err(|e| warn!("Unable to start the credential RPC on {}: {}", address, e))
+							.ok()
+					});
+					run_until_exit(runtime, service, exit)
+				},
 			}.map_err(|e| format!("{:?}", e))
 		}
-	).map_err(Into::into).map(|_| ())
+	)?;
+
+	match custom {
+		Some(CustomCommand::ExportVc(cmd)) => cmd.run(),
+		Some(CustomCommand::Present(cmd)) => cmd.run(),
+		None => Ok(()),
+	}
+}
+
+/// Flags of the node in addition to the standard Substrate ones.
+#[derive(Debug, StructOpt, Clone)]
+pub struct RunParams {
+	/// Address the credential RPC server of a full node listens on.
+	#[structopt(long = "vc-rpc-addr", default_value = "127.0.0.1:9934")]
+	vc_rpc_addr: SocketAddr,
+
+	/// Do not start the credential RPC server.
+	#[structopt(long = "no-vc-rpc")]
+	no_vc_rpc: bool,
+}
+
+impl_augment_clap!(RunParams);
+
+impl GetLogFilter for RunParams {
+	fn get_log_filter(&self) -> Option<String> {
+		None
+	}
+}
+
+impl RunParams {
+	/// The address to serve the credential RPC on, if it is enabled.
+	fn vc_rpc_address(&self) -> Option<SocketAddr> {
+		if self.no_vc_rpc {
+			None
+		} else {
+			Some(self.vc_rpc_addr)
+		}
+	}
+}
+
+/// Subcommands in addition to the standard Substrate ones.
+#[derive(Debug, StructOpt, Clone)]
+pub enum CustomCommand {
+	/// Export an on-chain credential as a W3C Verifiable Credential JSON-LD document.
+	#[structopt(name = "export-vc")]
+	ExportVc(ExportVcCmd),
+
+	/// Present credentials to a verifier, signed with the holder's key.
+	#[structopt(name = "present")]
+	Present(PresentCmd),
+}
+
+impl GetLogFilter for CustomCommand {
+	fn get_log_filter(&self) -> Option<String> {
+		None
+	}
+}
+
+/// The `export-vc` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportVcCmd {
+	/// SS58 address of the credential holder.
+	#[structopt(long = "holder")]
+	holder: String,
+
+	/// The subject of the credential.
+	#[structopt(long = "subject")]
+	subject: u32,
+
+	/// Credential RPC endpoint of the node to read the credential from.
+	#[structopt(long = "rpc-url", default_value = "http://127.0.0.1:9934")]
+	rpc_url: String,
+}
+
+impl ExportVcCmd {
+	/// Fetch the credential from the node and print it as JSON-LD.
+	fn run(self) -> error::Result<()> {
+		let vc = rpc_call(&self.rpc_url, "vc_exportCredential", json!([self.holder, self.subject]))?;
+		if vc.is_null() {
+			return Err("Credential not issued.".into());
+		}
+
+		let document = serde_json::to_string_pretty(&vc).map_err(|e| format!("{}", e))?;
+		println!("{}", document);
+		Ok(())
+	}
+}
+
+/// The `present` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct PresentCmd {
+	/// Secret URI of the holder's sr25519 key, e.g. `//Alice`.
+	#[structopt(long = "suri")]
+	suri: String,
+
+	/// The subjects of the presented credentials.
+	#[structopt(long = "subject")]
+	subjects: Vec<u32>,
+
+	/// Hex-encoded challenge supplied by the verifier.
+	#[structopt(long = "challenge")]
+	challenge: String,
+
+	/// Standard RPC endpoint of a node of the chain, to read its genesis hash from.
+	#[structopt(long = "node-rpc-url", default_value = "http://127.0.0.1:9933")]
+	node_rpc_url: String,
+}
+
+impl PresentCmd {
+	/// Sign the presentation and print it SCALE-encoded, ready for `vc_verifyPresentation`.
+	fn run(self) -> error::Result<()> {
+		let pair = sr25519::Pair::from_string(&self.suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+		let challenge = from_hex(self.challenge.trim_start_matches("0x"))
+			.map_err(|e| format!("Invalid challenge: {:?}", e))?;
+
+		let genesis_hash = rpc_call(&self.node_rpc_url, "chain_getBlockHash", json!([0]))?;
+		let genesis_hash: Hash = genesis_hash.as_str()
+			.and_then(|h| h.trim_start_matches("0x").parse().ok())
+			.ok_or("Invalid genesis hash.")?;
+
+		let presentation = presentation::present(&pair, &genesis_hash, &self.subjects, challenge);
+		println!("0x{}", HexDisplay::from(&presentation.encode()));
+		Ok(())
+	}
+}
+
+/// Call a JSON-RPC method over HTTP and return its result.
+fn rpc_call(url: &str, method: &str, params: Value) -> error::Result<Value> {
+	let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+	let request = hyper::Request::post(url)
+		.header("Content-Type", "application/json")
+		.body(hyper::Body::from(body.to_string()))
+		.map_err(|e| format!("Invalid RPC request: {}", e))?;
+
+	let response = hyper::Client::new()
+		.request(request)
+		.and_then(|response| response.into_body().concat2());
+	let mut runtime = Runtime::new().map_err(|e| format!("{:?}", e))?;
+	let bytes = runtime.block_on(response).map_err(|e| format!("RPC request failed: {}", e))?;
+
+	let response: Value = serde_json::from_slice(&bytes).map_err(|e| format!("Invalid RPC response: {}", e))?;
+	if let Some(error) = response.get("error") {
+		return Err(format!("RPC error: {}", error).into());
+	}
+	Ok(response["result"].clone())
 }
 
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
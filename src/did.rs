@@ -0,0 +1,170 @@
+//! Resolution of `did:substrate` DIDs to DID documents.
+//!
+//! See the W3C DID Core specification: https://www.w3.org/TR/did-core/
+
+use primitives::hexdisplay::HexDisplay;
+use serde_json::{json, Value};
+use substrate_verifiable_credentials_runtime::{
+	AccountId,
+	did::{Did, DidDocument, DidKey, KeyType},
+};
+
+/// JSON-LD context of the DID Core specification.
+pub const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+
+/// Prefix of DIDs of the `did:substrate` method.
+pub const DID_PREFIX: &str = "did:substrate:";
+
+/// Format a DID as a `did:substrate` URI.
+pub fn did_to_string(did: &Did) -> String {
+	format!("{}{}", DID_PREFIX, HexDisplay::from(&did.0))
+}
+
+/// Parse a `did:substrate` URI.
+pub fn parse_did(did: &str) -> Option<Did> {
+	if !did.starts_with(DID_PREFIX) {
+		return None;
+	}
+	did[DID_PREFIX.len()..].parse().ok()
+}
+
+/// Build the DID resolution result for a DID and its on-chain document, if any.
+pub fn resolve(did: &Did, document: Option<DidDocument<AccountId>>) -> Value {
+	match document {
+		Some(document) => json!({
+			"didDocument": document_to_json(did, &document),
+			"didResolutionMetadata": { "contentType": "application/did+ld+json" },
+			"didDocumentMetadata": { "deactivated": document.deactivated },
+		}),
+		None => json!({
+			"didDocument": null,
+			"didResolutionMetadata": { "error": "notFound" },
+			"didDocumentMetadata": {},
+		}),
+	}
+}
+
+/// Convert an on-chain DID document to a DID Core JSON-LD document.
+///
+/// The controlling account is published as the `#controller` key, the only key
+/// able to update the document. The document's keys can authenticate as the DID
+/// and sign credentials on its behalf.
+pub fn document_to_json(did: &Did, document: &DidDocument<AccountId>) -> Value {
+	let id = did_to_string(did);
+	let controller = json!({
+		"id": format!("{}#controller", id),
+		"type": "Sr25519VerificationKey2020",
+		"controller": id,
+		"publicKeyMultibase": multibase(&document.controller.0),
+	});
+
+	let mut methods = vec![controller];
+	methods.extend(document.keys.iter().map(|key| key_to_json(&id, key)));
+	let key_ids: Vec<String> = document.keys.iter().map(|key| fragment_uri(&id, &key.id)).collect();
+
+	json!({
+		"@context": [DID_CONTEXT],
+		"id": id,
+		"verificationMethod": methods,
+		"authentication": key_ids,
+		"assertionMethod": key_ids,
+		"capabilityInvocation": [format!("{}#controller", id)],
+		"service": document.services.iter().map(|service| json!({
+			"id": fragment_uri(&id, &service.id),
+			"type": String::from_utf8_lossy(&service.service_type),
+			"serviceEndpoint": String::from_utf8_lossy(&service.endpoint),
+		})).collect::<Vec<_>>(),
+	})
+}
+
+fn key_to_json(id: &str, key: &DidKey) -> Value {
+	let key_type = match key.key_type {
+		KeyType::Sr25519 => "Sr25519VerificationKey2020",
+		KeyType::Ed25519 => "Ed25519VerificationKey2020",
+	};
+	json!({
+		"id": fragment_uri(id, &key.id),
+		"type": key_type,
+		"controller": id,
+		"publicKeyMultibase": multibase(&key.public_key.0),
+	})
+}
+
+fn fragment_uri(id: &str, fragment: &[u8]) -> String {
+	format!("{}#{}", id, String::from_utf8_lossy(fragment))
+}
+
+/// Multibase base58btc encoding of a public key.
+fn multibase(key: &[u8]) -> String {
+	format!("z{}", bs58::encode(key).into_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_verifiable_credentials_runtime::did::DidService;
+
+	fn document() -> DidDocument<AccountId> {
+		DidDocument {
+			controller: AccountId::from_raw([1; 32]),
+			keys: vec![DidKey { id: b"key-1".to_vec(), key_type: KeyType::Ed25519, public_key: [2; 32].into() }],
+			services: vec![DidService {
+				id: b"hub".to_vec(),
+				service_type: b"LinkedDomains".to_vec(),
+				endpoint: b"https://example.com".to_vec(),
+			}],
+			deactivated: false,
+		}
+	}
+
+	#[test]
+	fn should_parse_formatted_did() {
+		let did = Did::repeat_byte(0xab);
+		let uri = did_to_string(&did);
+
+		assert_eq!(uri, format!("did:substrate:{}", "ab".repeat(32)));
+		assert_eq!(parse_did(&uri), Some(did));
+		assert_eq!(parse_did(&format!("did:example:{}", "ab".repeat(32))), None);
+		assert_eq!(parse_did("did:substrate:xyz"), None);
+	}
+
+	#[test]
+	fn should_convert_document_to_did_core() {
+		let did = Did::repeat_byte(0xab);
+		let id = did_to_string(&did);
+		let json = document_to_json(&did, &document());
+
+		assert_eq!(json["@context"], json!([DID_CONTEXT]));
+		assert_eq!(json["id"], json!(id));
+		assert_eq!(json["verificationMethod"], json!([
+			{
+				"id": format!("{}#controller", id),
+				"type": "Sr25519VerificationKey2020",
+				"controller": id,
+				"publicKeyMultibase": multibase(&[1; 32]),
+			},
+			{
+				"id": format!("{}#key-1", id),
+				"type": "Ed25519VerificationKey2020",
+				"controller": id,
+				"publicKeyMultibase": multibase(&[2; 32]),
+			},
+		]));
+		assert_eq!(json["authentication"], json!([format!("{}#key-1", id)]));
+		assert_eq!(json["assertionMethod"], json!([format!("{}#key-1", id)]));
+		assert_eq!(json["capabilityInvocation"], json!([format!("{}#controller", id)]));
+		assert_eq!(json["service"], json!([{
+			"id": format!("{}#hub", id),
+			"type": "LinkedDomains",
+			"serviceEndpoint": "https://example.com",
+		}]));
+	}
+
+	#[test]
+	fn should_resolve_missing_did() {
+		let resolution = resolve(&Did::repeat_byte(0xab), None);
+
+		assert_eq!(resolution["didDocument"], Value::Null);
+		assert_eq!(resolution["didResolutionMetadata"]["error"], json!("notFound"));
+	}
+}
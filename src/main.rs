@@ -6,6 +6,10 @@
 mod chain_spec;
 mod service;
 mod cli;
+mod rpc;
+mod w3c;
+mod did;
+mod presentation;
 
 pub use substrate_cli::{VersionInfo, IntoExit, error};
 
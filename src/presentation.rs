@@ -0,0 +1,182 @@
+//! Verifiable presentations: a holder proving possession of credentials to a verifier.
+//!
+//! The verifier hands the holder a fresh challenge. The holder answers with a
+//! presentation referencing its credentials and the challenge, signed with the
+//! holder's sr25519 account key. The verifier checks the signature and then,
+//! through the runtime, that every referenced credential still verifies.
+
+use std::fmt;
+
+use parity_codec::{Decode, Encode};
+use primitives::{sr25519, Blake2Hasher, Pair};
+use substrate_client::{self as client, Client, CallExecutor, runtime_api::ProvideRuntimeApi};
+use substrate_verifiable_credentials_runtime::{
+	AccountId, Hash,
+	opaque::{Block, BlockId},
+	verifiablecreds::Subject,
+	verifiablecreds_api::VerifiableCredsApi,
+};
+
+/// Domain separation tag of the signed presentation payload.
+const PRESENTATION_TAG: &[u8] = b"substrate-verifiable-credentials:presentation";
+
+/// A presentation of credentials, signed by their holder.
+#[derive(Encode, Decode, Clone, PartialEq, Debug)]
+pub struct Presentation {
+	/// The presenting holder, whose key signs the presentation.
+	pub holder: AccountId,
+	/// The presented credentials, as (holder, subject) references.
+	pub credentials: Vec<(AccountId, Subject)>,
+	/// The challenge supplied by the verifier.
+	pub challenge: Vec<u8>,
+	pub signature: sr25519::Signature,
+}
+
+/// Why a presentation does not verify.
+#[derive(Debug, PartialEq)]
+pub enum PresentationError {
+	/// The presentation answers another challenge.
+	ChallengeMismatch,
+	/// A referenced credential belongs to another holder.
+	HolderMismatch(AccountId, Subject),
+	/// The signature is not the holder's.
+	BadSignature,
+	/// A referenced credential does not verify.
+	InvalidCredential(Subject, String),
+	/// The runtime could not be queried.
+	Client(String),
+}
+
+impl fmt::Display for PresentationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PresentationError::ChallengeMismatch => write!(f, "Presentation does not answer the challenge."),
+			PresentationError::HolderMismatch(_, subject) =>
+				write!(f, "Presented credential for subject {} belongs to another holder.", subject),
+			PresentationError::BadSignature => write!(f, "Invalid holder signature."),
+			PresentationError::InvalidCredential(subject, reason) =>
+				write!(f, "Credential for subject {} does not verify: {}", subject, reason),
+			PresentationError::Client(e) => write!(f, "Unable to query the runtime: {}", e),
+		}
+	}
+}
+
+/// The payload signed by the holder. It is bound to the chain by its genesis
+/// hash so that a presentation cannot be replayed against another chain.
+pub fn payload(genesis_hash: &Hash, credentials: &[(AccountId, Subject)], challenge: &[u8]) -> Vec<u8> {
+	(PRESENTATION_TAG, genesis_hash, credentials, challenge).encode()
+}
+
+/// Present the holder's credentials for `subjects` in answer to `challenge`.
+pub fn present(pair: &sr25519::Pair, genesis_hash: &Hash, subjects: &[Subject], challenge: Vec<u8>) -> Presentation {
+	let holder = pair.public();
+	let credentials: Vec<_> = subjects.iter().map(|subject| (holder.clone(), *subject)).collect();
+	let signature = pair.sign(&payload(genesis_hash, &credentials, &challenge));
+
+	Presentation {
+		holder,
+		credentials,
+		challenge,
+		signature,
+	}
+}
+
+/// Check that the presentation answers `challenge` and is signed by the holder
+/// of every presented credential.
+pub fn verify_signature(presentation: &Presentation, genesis_hash: &Hash, challenge: &[u8]) -> Result<(), PresentationError> {
+	if presentation.challenge != challenge {
+		return Err(PresentationError::ChallengeMismatch);
+	}
+	if let Some((holder, subject)) = presentation.credentials.iter().find(|(h, _)| *h != presentation.holder) {
+		return Err(PresentationError::HolderMismatch(holder.clone(), *subject));
+	}
+
+	let message = payload(genesis_hash, &presentation.credentials, &presentation.challenge);
+	if !sr25519::Pair::verify(&presentation.signature, &message, &presentation.holder) {
+		return Err(PresentationError::BadSignature);
+	}
+
+	Ok(())
+}
+
+/// Verify a presentation: its signature, and that every presented credential
+/// still verifies at the best block.
+pub fn verify<B, E, RA>(
+	client: &Client<B, E, Block, RA>,
+	presentation: &Presentation,
+	challenge: &[u8],
+) -> Result<(), PresentationError> where
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: VerifiableCredsApi<Block>,
+{
+	let info = client.info().map_err(|e| PresentationError::Client(format!("{:?}", e)))?;
+	verify_signature(presentation, &info.chain.genesis_hash, challenge)?;
+
+	let at = BlockId::hash(info.chain.best_hash);
+	let api = client.runtime_api();
+	for (holder, subject) in &presentation.credentials {
+		api.verify(&at, holder.clone(), *subject)
+			.map_err(|e| PresentationError::Client(format!("{:?}", e)))?
+			.map_err(|e| PresentationError::InvalidCredential(*subject, String::from_utf8_lossy(&e).into_owned()))?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pair(suri: &str) -> sr25519::Pair {
+		sr25519::Pair::from_string(suri, None).expect("static values are valid secret URIs; qed")
+	}
+
+	fn genesis_hash() -> Hash {
+		Hash::repeat_byte(1)
+	}
+
+	#[test]
+	fn should_verify_presentation() {
+		let presentation = present(&pair("//Alice"), &genesis_hash(), &[1, 2], b"challenge".to_vec());
+		assert_eq!(presentation.credentials.len(), 2);
+		assert_eq!(verify_signature(&presentation, &genesis_hash(), b"challenge"), Ok(()));
+	}
+
+	#[test]
+	fn should_fail_verify_other_challenge() {
+		let presentation = present(&pair("//Alice"), &genesis_hash(), &[1], b"challenge".to_vec());
+		assert_eq!(
+			verify_signature(&presentation, &genesis_hash(), b"other"),
+			Err(PresentationError::ChallengeMismatch),
+		);
+	}
+
+	#[test]
+	fn should_fail_verify_tampered_presentation() {
+		let mut presentation = present(&pair("//Alice"), &genesis_hash(), &[1], b"challenge".to_vec());
+		assert_eq!(
+			verify_signature(&presentation, &Hash::repeat_byte(2), b"challenge"),
+			Err(PresentationError::BadSignature),
+		);
+
+		presentation.credentials[0].1 = 2;
+		assert_eq!(
+			verify_signature(&presentation, &genesis_hash(), b"challenge"),
+			Err(PresentationError::BadSignature),
+		);
+	}
+
+	#[test]
+	fn should_fail_verify_credential_of_other_holder() {
+		let mut presentation = present(&pair("//Alice"), &genesis_hash(), &[1], b"challenge".to_vec());
+		let bob = pair("//Bob").public();
+		presentation.credentials.push((bob.clone(), 2));
+		assert_eq!(
+			verify_signature(&presentation, &genesis_hash(), b"challenge"),
+			Err(PresentationError::HolderMismatch(bob, 2)),
+		);
+	}
+}
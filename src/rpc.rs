@@ -0,0 +1,449 @@
+//! JSON-RPC endpoints for querying verifiable credentials and resolving DIDs.
+//!
+//! The endpoints are backed by the `VerifiableCredsApi` and `DidApi` runtime APIs and
+//! return decoded JSON, so clients don't need to build storage keys.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use jsonrpc_core::{Error, ErrorCode, IoHandler, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_http_server::{Server, ServerBuilder};
+use log::info;
+use primitives::crypto::Ss58Codec;
+use primitives::hexdisplay::HexDisplay;
+use primitives::{Blake2Hasher, Bytes};
+use serde::Serialize;
+use parity_codec::Decode;
+use serde_json::Value;
+use substrate_client::{self as client, Client, CallExecutor, runtime_api::ProvideRuntimeApi};
+use crate::{did, presentation::{self, Presentation}, w3c};
+use substrate_verifiable_credentials_runtime::{
+	AccountId, Moment,
+	did_api::DidApi,
+	opaque::{Block, BlockId},
+	verifiablecreds::{Claim, ClaimType, ClaimValue, Credential, Retirement, Revocation, ReasonCode, StatusListId, Subject},
+	verifiablecreds_api::VerifiableCredsApi,
+};
+
+/// A credential as returned by the RPC.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialJson {
+	pub holder: String,
+	pub subject: Subject,
+	pub issuer: String,
+	/// The issuer's DID at the time of issuance, if any.
+	pub issuer_did: Option<String>,
+	/// The holder's DID at the time of acceptance, if any.
+	pub holder_did: Option<String>,
+	pub issued: Moment,
+	pub valid_from: Moment,
+	pub valid_until: Option<Moment>,
+	pub claims: BTreeMap<String, Value>,
+	pub suspended: bool,
+	/// Blake2-256 hash of the anchored off-chain document, if any.
+	pub document_hash: Option<String>,
+	/// Merkle root of the salted claims the holder can disclose one by one, if any.
+	pub claims_root: Option<String>,
+	pub revocation: Option<RevocationJson>,
+}
+
+/// The revocation of a credential as returned by the RPC.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevocationJson {
+	pub revoker: String,
+	pub revoked: Moment,
+	pub reason: ReasonCode,
+}
+
+/// The outcome of verifying a credential.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationJson {
+	pub valid: bool,
+	/// Why the credential does not verify, if it doesn't.
+	pub reason: Option<String>,
+	/// The retirement of the credential's subject, if retired.
+	pub subject_retirement: Option<RetirementJson>,
+	/// The accredited issuers from the root authority down to the credential's issuer,
+	/// if the issuer is accredited for the subject's category.
+	pub accreditation_chain: Option<Vec<String>>,
+}
+
+/// The outcome of verifying a presentation.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresentationVerificationJson {
+	pub valid: bool,
+	/// Why the presentation does not verify, if it doesn't.
+	pub reason: Option<String>,
+}
+
+/// The retirement of a subject as returned by the RPC.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetirementJson {
+	pub retired: Moment,
+	/// Whether existing credentials were invalidated rather than kept as historical.
+	pub invalidated: bool,
+}
+
+/// A subject as returned by the RPC.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectJson {
+	pub subject: Subject,
+	pub owner: String,
+	pub issuers: Vec<String>,
+	pub name: String,
+	pub description: String,
+	/// Claim schema, mapping field names to their type.
+	pub schema: Vec<SchemaFieldJson>,
+	/// Subjects holders must hold valid credentials for before being issued one for this subject.
+	pub prerequisites: Vec<Subject>,
+	/// Whether holders may transfer their credentials for this subject.
+	pub transferable: bool,
+	pub retirement: Option<RetirementJson>,
+}
+
+/// A field of a subject's claim schema as returned by the RPC.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaFieldJson {
+	pub name: String,
+	#[serde(rename = "type")]
+	pub ty: &'static str,
+	pub required: bool,
+}
+
+/// Credential query endpoints.
+#[rpc]
+pub trait VerifiableCredsRpc {
+	/// The credential `holder` has been issued for `subject`.
+	#[rpc(name = "vc_getCredential")]
+	fn get_credential(&self, holder: String, subject: Subject) -> Result<Option<CredentialJson>>;
+
+	/// Verify the credential `holder` has been issued for `subject`.
+	#[rpc(name = "vc_verify")]
+	fn verify(&self, holder: String, subject: Subject) -> Result<VerificationJson>;
+
+	/// Verify the credential `holder` has been issued for `subject` along with
+	/// the off-chain `document` presented for it.
+	#[rpc(name = "vc_verifyDocument")]
+	fn verify_document(&self, holder: String, subject: Subject, document: String) -> Result<VerificationJson>;
+
+	/// Verify a SCALE-encoded presentation answering `challenge`.
+	#[rpc(name = "vc_verifyPresentation")]
+	fn verify_presentation(&self, presentation: Bytes, challenge: Bytes) -> Result<PresentationVerificationJson>;
+
+	/// The credential `holder` has been issued for `subject` as a
+	/// W3C Verifiable Credential JSON-LD document.
+	#[rpc(name = "vc_exportCredential")]
+	fn export_credential(&self, holder: String, subject: Subject) -> Result<Option<Value>>;
+
+	/// A status list as a W3C StatusList2021 credential.
+	#[rpc(name = "vc_exportStatusList")]
+	fn export_status_list(&self, list: StatusListId) -> Result<Option<Value>>;
+
+	/// The `credentialStatus` entry for an off-chain credential
+	/// assigned the entry at `index` of a status list.
+	#[rpc(name = "vc_statusListEntry")]
+	fn status_list_entry(&self, list: StatusListId, index: u32) -> Result<Option<Value>>;
+
+	/// The owner, issuers and metadata of `subject`.
+	#[rpc(name = "vc_getSubject")]
+	fn get_subject(&self, subject: Subject) -> Result<Option<SubjectJson>>;
+
+	/// A page of the unrevoked credentials `holder` holds.
+	#[rpc(name = "vc_listHolderCredentials")]
+	fn list_holder_credentials(&self, holder: String, start: u32, limit: u32) -> Result<Vec<CredentialJson>>;
+
+	/// A page of the holders of unrevoked credentials for `subject`.
+	#[rpc(name = "vc_listSubjectHolders")]
+	fn list_subject_holders(&self, subject: Subject, start: u32, limit: u32) -> Result<Vec<String>>;
+
+	/// Resolve a `did:substrate` DID to its DID Core document and metadata.
+	#[rpc(name = "did_resolve")]
+	fn resolve_did(&self, did: String) -> Result<Value>;
+}
+
+/// Implementation of the credential RPC on top of a client.
+pub struct VerifiableCreds<B, E, RA> {
+	client: Arc<Client<B, E, Block, RA>>,
+}
+
+impl<B, E, RA> VerifiableCreds<B, E, RA> {
+	/// Create the RPC handler for the given client.
+	pub fn new(client: Arc<Client<B, E, Block, RA>>) -> Self {
+		VerifiableCreds { client }
+	}
+}
+
+impl<B, E, RA> VerifiableCreds<B, E, RA> where
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: VerifiableCredsApi<Block> + DidApi<Block>,
+{
+	fn best_block(&self) -> Result<BlockId> {
+		let info = self.client.info().map_err(client_error)?;
+		Ok(BlockId::hash(info.chain.best_hash))
+	}
+
+	fn verification_json(
+		&self,
+		at: &BlockId,
+		holder: AccountId,
+		subject: Subject,
+		result: std::result::Result<(), Vec<u8>>,
+	) -> Result<VerificationJson> {
+		let api = self.client.runtime_api();
+		let retirement = api.subject_retirement(at, subject).map_err(client_error)?;
+		let chain = api.accreditation_chain(at, holder, subject).map_err(client_error)?;
+		Ok(VerificationJson {
+			valid: result.is_ok(),
+			reason: result.err().map(|e| String::from_utf8_lossy(&e).into_owned()),
+			subject_retirement: retirement.map(retirement_to_json),
+			accreditation_chain: chain.map(|chain| chain.iter().map(|a| a.to_ss58check()).collect()),
+		})
+	}
+
+	fn credential_json(&self, at: &BlockId, holder: &AccountId, subject: Subject) -> Result<Option<CredentialJson>> {
+		let api = self.client.runtime_api();
+		let credential = api.credential(at, holder.clone(), subject).map_err(client_error)?;
+		let revocation = api.revocation(at, holder.clone(), subject).map_err(client_error)?;
+		Ok(credential.map(|c| credential_to_json(holder, c, revocation)))
+	}
+}
+
+impl<B, E, RA> VerifiableCredsRpc for VerifiableCreds<B, E, RA> where
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: VerifiableCredsApi<Block> + DidApi<Block>,
+{
+	fn get_credential(&self, holder: String, subject: Subject) -> Result<Option<CredentialJson>> {
+		let holder = parse_account(&holder)?;
+		let at = self.best_block()?;
+		self.credential_json(&at, &holder, subject)
+	}
+
+	fn verify(&self, holder: String, subject: Subject) -> Result<VerificationJson> {
+		let holder = parse_account(&holder)?;
+		let at = self.best_block()?;
+		let result = self.client.runtime_api().verify(&at, holder.clone(), subject).map_err(client_error)?;
+		self.verification_json(&at, holder, subject, result)
+	}
+
+	fn verify_document(&self, holder: String, subject: Subject, document: String) -> Result<VerificationJson> {
+		let holder = parse_account(&holder)?;
+		let at = self.best_block()?;
+		let result = self.client.runtime_api()
+			.verify_document(&at, holder.clone(), subject, document.into_bytes())
+			.map_err(client_error)?;
+		self.verification_json(&at, holder, subject, result)
+	}
+
+	fn verify_presentation(&self, presentation: Bytes, challenge: Bytes) -> Result<PresentationVerificationJson> {
+		let presentation = Presentation::decode(&mut &presentation[..])
+			.ok_or_else(|| Error::invalid_params("Invalid presentation encoding."))?;
+		let result = presentation::verify(&self.client, &presentation, &challenge);
+		Ok(PresentationVerificationJson {
+			valid: result.is_ok(),
+			reason: result.err().map(|e| e.to_string()),
+		})
+	}
+
+	fn export_credential(&self, holder: String, subject: Subject) -> Result<Option<Value>> {
+		let holder = parse_account(&holder)?;
+		let info = self.client.info().map_err(client_error)?;
+		let at = BlockId::hash(info.chain.best_hash);
+		let api = self.client.runtime_api();
+
+		let credential = match api.credential(&at, holder.clone(), subject).map_err(client_error)? {
+			Some(credential) => credential,
+			None => return Ok(None),
+		};
+		let subject_info = api.subject_info(&at, subject).map_err(client_error)?;
+		w3c::credential_to_vc(&info.chain.genesis_hash, &holder, &credential, &subject_info)
+			.map(Some)
+			.map_err(export_error)
+	}
+
+	fn export_status_list(&self, list: StatusListId) -> Result<Option<Value>> {
+		let info = self.client.info().map_err(client_error)?;
+		let at = BlockId::hash(info.chain.best_hash);
+		let api = self.client.runtime_api();
+
+		let status_list = match api.status_list(&at, list).map_err(client_error)? {
+			Some(status_list) => status_list,
+			None => return Ok(None),
+		};
+		let owner_did = api.did_of(&at, status_list.owner.clone()).map_err(client_error)?;
+		w3c::status_list_to_vc(&info.chain.genesis_hash, list, &status_list, owner_did)
+			.map(Some)
+			.map_err(export_error)
+	}
+
+	fn status_list_entry(&self, list: StatusListId, index: u32) -> Result<Option<Value>> {
+		let info = self.client.info().map_err(client_error)?;
+		let at = BlockId::hash(info.chain.best_hash);
+		let status_list = self.client.runtime_api().status_list(&at, list).map_err(client_error)?;
+		Ok(status_list
+			.filter(|status_list| index < status_list.size)
+			.map(|_| w3c::status_list_entry(&info.chain.genesis_hash, list, index)))
+	}
+
+	fn get_subject(&self, subject: Subject) -> Result<Option<SubjectJson>> {
+		let at = self.best_block()?;
+		let api = self.client.runtime_api();
+		let owner = match api.subject_issuer(&at, subject).map_err(client_error)? {
+			Some(owner) => owner,
+			None => return Ok(None),
+		};
+		let issuers = api.subject_issuers(&at, subject).map_err(client_error)?;
+		let info = api.subject_info(&at, subject).map_err(client_error)?;
+		let retirement = api.subject_retirement(&at, subject).map_err(client_error)?;
+		let prerequisites = api.subject_prerequisites(&at, subject).map_err(client_error)?;
+		let transferable = api.is_transferable(&at, subject).map_err(client_error)?;
+
+		Ok(Some(SubjectJson {
+			subject,
+			owner: owner.to_ss58check(),
+			issuers: issuers.iter().map(|i| i.to_ss58check()).collect(),
+			name: String::from_utf8_lossy(&info.name).into_owned(),
+			description: String::from_utf8_lossy(&info.description).into_owned(),
+			schema: info.schema.into_iter().map(|f| SchemaFieldJson {
+				name: String::from_utf8_lossy(&f.name).into_owned(),
+				ty: claim_type_name(f.ty),
+				required: f.required,
+			}).collect(),
+			prerequisites: prerequisites.subjects,
+			transferable,
+			retirement: retirement.map(retirement_to_json),
+		}))
+	}
+
+	fn list_holder_credentials(&self, holder: String, start: u32, limit: u32) -> Result<Vec<CredentialJson>> {
+		let holder = parse_account(&holder)?;
+		let at = self.best_block()?;
+		let subjects = self.client.runtime_api()
+			.holder_credentials(&at, holder.clone(), start, limit)
+			.map_err(client_error)?;
+
+		let mut credentials = Vec::with_capacity(subjects.len());
+		for subject in subjects {
+			credentials.extend(self.credential_json(&at, &holder, subject)?);
+		}
+		Ok(credentials)
+	}
+
+	fn list_subject_holders(&self, subject: Subject, start: u32, limit: u32) -> Result<Vec<String>> {
+		let at = self.best_block()?;
+		let holders = self.client.runtime_api()
+			.subject_holders(&at, subject, start, limit)
+			.map_err(client_error)?;
+		Ok(holders.iter().map(|h| h.to_ss58check()).collect())
+	}
+
+	fn resolve_did(&self, uri: String) -> Result<Value> {
+		let id = did::parse_did(&uri).ok_or_else(|| Error::invalid_params("Invalid did:substrate DID."))?;
+		let at = self.best_block()?;
+		let document = self.client.runtime_api().document(&at, id).map_err(client_error)?;
+		Ok(did::resolve(&id, document))
+	}
+}
+
+/// Start an HTTP server serving the credential RPC.
+pub fn start_http<B, E, RA>(addr: &SocketAddr, client: Arc<Client<B, E, Block, RA>>) -> std::io::Result<Server> where
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: VerifiableCredsApi<Block> + DidApi<Block>,
+{
+	let mut io = IoHandler::new();
+	io.extend_with(VerifiableCreds::new(client).to_delegate());
+
+	let server = ServerBuilder::new(io).start_http(addr)?;
+	info!("Verifiable credentials RPC listening on http://{}", addr);
+	Ok(server)
+}
+
+/// Convert a credential to its JSON representation.
+pub fn credential_to_json(
+	holder: &AccountId,
+	credential: Credential<Moment, AccountId>,
+	revocation: Option<Revocation<Moment, AccountId>>,
+) -> CredentialJson {
+	CredentialJson {
+		holder: holder.to_ss58check(),
+		subject: credential.subject,
+		issuer: credential.by.to_ss58check(),
+		issuer_did: credential.issuer_did.as_ref().map(did::did_to_string),
+		holder_did: credential.holder_did.as_ref().map(did::did_to_string),
+		issued: credential.when,
+		valid_from: credential.valid_from,
+		valid_until: credential.valid_until,
+		claims: credential.claims.into_iter().map(claim_to_json).collect(),
+		suspended: credential.suspended,
+		document_hash: credential.document_hash.map(|h| format!("{:?}", h)),
+		claims_root: credential.claims_root.map(|h| format!("{:?}", h)),
+		revocation: revocation.map(|r| RevocationJson {
+			revoker: r.by.to_ss58check(),
+			revoked: r.when,
+			reason: r.reason,
+		}),
+	}
+}
+
+fn retirement_to_json(retirement: Retirement<Moment>) -> RetirementJson {
+	RetirementJson {
+		retired: retirement.when,
+		invalidated: retirement.invalidated,
+	}
+}
+
+pub(crate) fn claim_to_json(claim: Claim) -> (String, Value) {
+	let value = match claim.value {
+		ClaimValue::Bool(b) => Value::Bool(b),
+		ClaimValue::U64(n) => Value::from(n),
+		ClaimValue::Text(t) => Value::String(String::from_utf8_lossy(&t).into_owned()),
+		ClaimValue::Bytes(b) => Value::String(format!("0x{}", HexDisplay::from(&b))),
+	};
+	(String::from_utf8_lossy(&claim.key).into_owned(), value)
+}
+
+fn claim_type_name(ty: ClaimType) -> &'static str {
+	match ty {
+		ClaimType::Bool => "bool",
+		ClaimType::U64 => "u64",
+		ClaimType::Text => "text",
+		ClaimType::Bytes => "bytes",
+	}
+}
+
+fn parse_account(address: &str) -> Result<AccountId> {
+	AccountId::from_ss58check(address).map_err(|_| Error::invalid_params("Invalid SS58 address."))
+}
+
+fn client_error<E: std::fmt::Debug>(e: E) -> Error {
+	Error {
+		code: ErrorCode::InternalError,
+		message: "Unable to query the runtime.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn export_error(e: String) -> Error {
+	Error {
+		code: ErrorCode::InternalError,
+		message: "Unable to export as a W3C Verifiable Credential.".into(),
+		data: Some(e.into()),
+	}
+}
@@ -0,0 +1,257 @@
+//! Conversion of on-chain credentials to W3C Verifiable Credentials.
+//!
+//! See the W3C Verifiable Credentials Data Model: https://www.w3.org/TR/vc-data-model/
+
+use std::convert::TryFrom;
+use std::io::Write;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use flate2::{write::GzEncoder, Compression};
+use primitives::crypto::Ss58Codec;
+use serde_json::{json, Map, Value};
+use substrate_verifiable_credentials_runtime::{
+	AccountId, Hash, Moment,
+	did::Did,
+	verifiablecreds::{Credential, StatusList, StatusListId, Subject, SubjectInfo},
+};
+
+use crate::did::did_to_string;
+use crate::rpc::claim_to_json;
+
+/// JSON-LD context of the W3C Verifiable Credentials Data Model.
+pub const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// JSON-LD context of the W3C Status List 2021 specification.
+pub const STATUS_LIST_CONTEXT: &str = "https://w3id.org/vc/status-list/2021/v1";
+
+/// Type of the `credentialStatus` entry pointing back at the on-chain record.
+pub const STATUS_TYPE: &str = "SubstrateVerifiableCredsStatus";
+
+/// URI identifying an account.
+pub fn account_uri(account: &AccountId) -> String {
+	format!("urn:substrate:account:{}", account.to_ss58check())
+}
+
+/// URI identifying the on-chain record of the credential `holder` holds for `subject`
+/// on the chain with the given genesis hash.
+pub fn credential_uri(genesis_hash: &Hash, holder: &AccountId, subject: Subject) -> String {
+	format!("urn:substrate:{:?}:credential:{}:{}", genesis_hash, holder.to_ss58check(), subject)
+}
+
+/// URI identifying an on-chain status list on the chain with the given genesis hash.
+pub fn status_list_uri(genesis_hash: &Hash, list: StatusListId) -> String {
+	format!("urn:substrate:{:?}:status-list:{}", genesis_hash, list)
+}
+
+/// Convert an on-chain credential and its subject's metadata to a W3C
+/// Verifiable Credential JSON-LD document.
+///
+/// The issuer and holder are identified by their `did:substrate` DIDs where the
+/// credential records them, and by their account URIs otherwise.
+///
+/// The document is unsigned: its proof is the on-chain record referenced by `credentialStatus`,
+/// which verifiers check against the chain, e.g. via `vc_verify`.
+///
+/// Fails if a timestamp of the credential cannot be represented as a date.
+pub fn credential_to_vc(
+	genesis_hash: &Hash,
+	holder: &AccountId,
+	credential: &Credential<Moment, AccountId>,
+	subject: &SubjectInfo,
+) -> Result<Value, String> {
+	let id = credential_uri(genesis_hash, holder, credential.subject);
+
+	let issuer_id = credential.issuer_did.as_ref().map(did_to_string).unwrap_or_else(|| account_uri(&credential.by));
+	let holder_id = credential.holder_did.as_ref().map(did_to_string).unwrap_or_else(|| account_uri(holder));
+
+	let mut credential_subject = Map::new();
+	credential_subject.insert("id".into(), holder_id.into());
+	credential_subject.extend(credential.claims.iter().cloned().map(claim_to_json));
+
+	let mut types = vec![Value::from("VerifiableCredential")];
+	if !subject.name.is_empty() {
+		types.push(String::from_utf8_lossy(&subject.name).into_owned().into());
+	}
+
+	let mut vc = json!({
+		"@context": [CREDENTIALS_CONTEXT],
+		"id": id,
+		"type": types,
+		"issuer": issuer_id,
+		"issuanceDate": timestamp_to_rfc3339(credential.when)?,
+		"credentialSubject": credential_subject,
+		"credentialStatus": {
+			"id": id,
+			"type": STATUS_TYPE,
+			"holder": holder.to_ss58check(),
+			"subject": credential.subject,
+		},
+	});
+	if !subject.description.is_empty() {
+		vc["description"] = String::from_utf8_lossy(&subject.description).into_owned().into();
+	}
+	if let Some(valid_until) = credential.valid_until {
+		vc["expirationDate"] = timestamp_to_rfc3339(valid_until)?.into();
+	}
+
+	Ok(vc)
+}
+
+/// Convert an on-chain status list to a W3C StatusList2021 credential.
+///
+/// See the W3C Status List 2021 specification: https://www.w3.org/TR/vc-status-list/
+///
+/// Fails if the list's update time cannot be represented as a date.
+pub fn status_list_to_vc(
+	genesis_hash: &Hash,
+	list: StatusListId,
+	status_list: &StatusList<Moment, AccountId>,
+	owner_did: Option<Did>,
+) -> Result<Value, String> {
+	let id = status_list_uri(genesis_hash, list);
+	Ok(json!({
+		"@context": [CREDENTIALS_CONTEXT, STATUS_LIST_CONTEXT],
+		"id": id,
+		"type": ["VerifiableCredential", "StatusList2021Credential"],
+		"issuer": owner_did.as_ref().map(did_to_string).unwrap_or_else(|| account_uri(&status_list.owner)),
+		"issuanceDate": timestamp_to_rfc3339(status_list.updated)?,
+		"credentialSubject": {
+			"id": format!("{}#list", id),
+			"type": "StatusList2021",
+			"statusPurpose": "revocation",
+			"encodedList": encode_status_list(&status_list.bits),
+		},
+	}))
+}
+
+/// The `credentialStatus` entry of an off-chain credential assigned
+/// the entry at `index` of a status list.
+pub fn status_list_entry(genesis_hash: &Hash, list: StatusListId, index: u32) -> Value {
+	let list_id = status_list_uri(genesis_hash, list);
+	json!({
+		"id": format!("{}#{}", list_id, index),
+		"type": "StatusList2021Entry",
+		"statusPurpose": "revocation",
+		"statusListIndex": index.to_string(),
+		"statusListCredential": list_id,
+	})
+}
+
+/// GZIP-compress and base64url-encode a status list bitmap.
+fn encode_status_list(bits: &[u8]) -> String {
+	let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+	encoder.write_all(bits).expect("writing to a Vec does not fail; qed");
+	let compressed = encoder.finish().expect("writing to a Vec does not fail; qed");
+	base64::encode_config(&compressed, base64::URL_SAFE_NO_PAD)
+}
+
+/// Format a timestamp in seconds since the unix epoch as RFC 3339.
+/// Timestamps are set by issuers, so they may lie beyond the representable dates.
+fn timestamp_to_rfc3339(timestamp: Moment) -> Result<String, String> {
+	i64::try_from(timestamp).ok()
+		.and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+		.map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+		.ok_or_else(|| format!("Timestamp out of range: {}", timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Read;
+	use flate2::read::GzDecoder;
+	use substrate_verifiable_credentials_runtime::verifiablecreds::{Claim, ClaimValue};
+
+	fn account(byte: u8) -> AccountId {
+		AccountId::from_raw([byte; 32])
+	}
+
+	fn credential(valid_until: Option<Moment>) -> Credential<Moment, AccountId> {
+		Credential {
+			subject: 1,
+			when: 1_500_000_000,
+			by: account(2),
+			valid_until,
+			claims: vec![Claim { key: b"class".to_vec(), value: ClaimValue::Text(b"B".to_vec()) }],
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn should_export_credential() {
+		let subject = SubjectInfo { name: b"DrivingLicence".to_vec(), ..Default::default() };
+		let vc = credential_to_vc(&Hash::zero(), &account(1), &credential(Some(1_600_000_000)), &subject).unwrap();
+
+		assert_eq!(vc["@context"], json!([CREDENTIALS_CONTEXT]));
+		assert_eq!(vc["id"], json!(credential_uri(&Hash::zero(), &account(1), 1)));
+		assert_eq!(vc["type"], json!(["VerifiableCredential", "DrivingLicence"]));
+		assert_eq!(vc["issuer"], json!(account_uri(&account(2))));
+		assert_eq!(vc["issuanceDate"], json!("2017-07-14T02:40:00Z"));
+		assert_eq!(vc["expirationDate"], json!("2020-09-13T12:26:40Z"));
+		assert_eq!(vc["credentialSubject"]["id"], json!(account_uri(&account(1))));
+		assert_eq!(vc["credentialSubject"]["class"], json!("B"));
+		assert_eq!(vc["credentialStatus"]["type"], json!(STATUS_TYPE));
+	}
+
+	#[test]
+	fn should_identify_issuer_by_did() {
+		let mut credential = credential(None);
+		credential.issuer_did = Some(Hash::repeat_byte(1));
+		let vc = credential_to_vc(&Hash::zero(), &account(1), &credential, &Default::default()).unwrap();
+
+		assert_eq!(vc["issuer"], json!(did_to_string(&Hash::repeat_byte(1))));
+		assert_eq!(vc["type"], json!(["VerifiableCredential"]));
+		assert!(vc.get("expirationDate").is_none());
+	}
+
+	#[test]
+	fn should_fail_export_with_out_of_range_timestamp() {
+		for valid_until in vec![u64::max_value(), i64::max_value() as u64] {
+			assert!(credential_to_vc(&Hash::zero(), &account(1), &credential(Some(valid_until)), &Default::default()).is_err());
+		}
+	}
+
+	/// Decode an `encodedList` back to the status list bitmap.
+	fn decode_status_list(encoded: &str) -> Vec<u8> {
+		let compressed = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD).unwrap();
+		let mut bits = Vec::new();
+		GzDecoder::new(&compressed[..]).read_to_end(&mut bits).unwrap();
+		bits
+	}
+
+	#[test]
+	fn should_export_status_list() {
+		// Entries 0, 9 and 15 are revoked, the first entry being the most significant bit.
+		let status_list = StatusList {
+			subject: 1,
+			owner: account(2),
+			size: 16,
+			bits: vec![0b1000_0000, 0b0100_0001],
+			updated: 1_500_000_000,
+		};
+		let vc = status_list_to_vc(&Hash::zero(), 3, &status_list, None).unwrap();
+
+		assert_eq!(vc["@context"], json!([CREDENTIALS_CONTEXT, STATUS_LIST_CONTEXT]));
+		assert_eq!(vc["id"], json!(status_list_uri(&Hash::zero(), 3)));
+		assert_eq!(vc["type"], json!(["VerifiableCredential", "StatusList2021Credential"]));
+		assert_eq!(vc["issuer"], json!(account_uri(&account(2))));
+		assert_eq!(vc["issuanceDate"], json!("2017-07-14T02:40:00Z"));
+		assert_eq!(vc["credentialSubject"]["type"], json!("StatusList2021"));
+		assert_eq!(vc["credentialSubject"]["statusPurpose"], json!("revocation"));
+
+		let bits = decode_status_list(vc["credentialSubject"]["encodedList"].as_str().unwrap());
+		assert_eq!(bits, status_list.bits);
+		let revoked: Vec<usize> = (0..16).filter(|i| bits[i / 8] & (0x80 >> (i % 8)) != 0).collect();
+		assert_eq!(revoked, vec![0, 9, 15]);
+	}
+
+	#[test]
+	fn should_reference_status_list_entry() {
+		let entry = status_list_entry(&Hash::zero(), 3, 9);
+		let list_id = status_list_uri(&Hash::zero(), 3);
+
+		assert_eq!(entry["id"], json!(format!("{}#9", list_id)));
+		assert_eq!(entry["type"], json!("StatusList2021Entry"));
+		assert_eq!(entry["statusListIndex"], json!("9"));
+		assert_eq!(entry["statusListCredential"], json!(list_id));
+	}
+}